@@ -1,15 +1,19 @@
 extern crate proc_macro;
 
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{
-    self, bracketed, parse::Parse, parse_macro_input, punctuated::Punctuated, token::Bracket,
-    FnArg, Ident, ItemFn, Token,
+    self, bracketed, parse::Parse, parse_macro_input, punctuated::Punctuated, spanned::Spanned,
+    token::Bracket, Attribute, Expr, FnArg, GenericArgument, Ident, ItemFn, Lit, LitStr, Meta,
+    MetaNameValue, NestedMeta, Pat, PathArguments, ReturnType, Token, Type,
 };
 
 #[proc_macro]
 pub fn library(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let macro_input = parse_macro_input!(item as MacroInput);
+    if let Err(error) = macro_input.validate() {
+        return error.to_compile_error().into();
+    }
     let generated_enum = macro_input.generate_enum();
     let generated_impl = macro_input.generate_impl();
     quote! {
@@ -19,6 +23,8 @@ pub fn library(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     .into()
 }
 struct MacroInput {
+    /// Options such as `#[namespace = "name"]`
+    attrs: Vec<Attribute>,
     name: Ident,
     _punct_1: Token![;],
     _import_bracket: Bracket,
@@ -26,12 +32,15 @@ struct MacroInput {
     _punct_2: Token![;],
     _function_bracket: Bracket,
     functions: Punctuated<ItemFn, Token![,]>,
+    /// Optional `; const [name: Type = value, ...]` section
+    constants: Punctuated<Constant, Token![,]>,
 }
 impl Parse for MacroInput {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let functions;
         let imports;
-        Ok(MacroInput {
+        let mut macro_input = MacroInput {
+            attrs: input.call(Attribute::parse_outer)?,
             name: input.parse()?,
             _punct_1: input.parse()?,
             _import_bracket: bracketed!(imports in input),
@@ -39,10 +48,85 @@ impl Parse for MacroInput {
             _punct_2: input.parse()?,
             _function_bracket: bracketed!(functions in input),
             functions: functions.parse_terminated(ItemFn::parse)?,
+            constants: Punctuated::new(),
+        };
+        if input.parse::<Option<Token![;]>>()?.is_some() && !input.is_empty() {
+            let constants;
+            input.parse::<Token![const]>()?;
+            bracketed!(constants in input);
+            macro_input.constants = constants.parse_terminated(Constant::parse)?;
+        }
+        Ok(macro_input)
+    }
+}
+/// Named constant, replaced by its value while parsing an expression
+struct Constant {
+    name: Ident,
+    _colon: Token![:],
+    ty: Type,
+    _eq: Token![=],
+    value: Expr,
+}
+impl Parse for Constant {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Constant {
+            name: input.parse()?,
+            _colon: input.parse()?,
+            ty: input.parse()?,
+            _eq: input.parse()?,
+            value: input.parse()?,
         })
     }
 }
 impl MacroInput {
+    /// Checks everything the generated code relies on,
+    /// so mistakes are reported at the offending tokens instead of inside the expansion.
+    pub(crate) fn validate(&self) -> syn::Result<()> {
+        let mut errors = Errors::default();
+        for attr in &self.attrs {
+            if attr.path.is_ident("namespace") {
+                errors.check(name_value(attr, "namespace"));
+            }
+        }
+        let mut names = Vec::new();
+        let mut context: Option<String> = None;
+        for item_fn in &self.functions {
+            errors.check(validate_function(item_fn));
+            for attr in &item_fn.attrs {
+                if attr.path.is_ident("name") || attr.path.is_ident("alias") {
+                    errors.check(name_value(attr, "function"));
+                }
+            }
+            for name in function_names(item_fn) {
+                if names.contains(&name) {
+                    errors.push(syn::Error::new_spanned(
+                        &item_fn.sig.ident,
+                        format!("a function named `{name}` is already defined in this library"),
+                    ));
+                }
+                names.push(name);
+            }
+            for arg in &item_fn.sig.inputs {
+                let reference = match arg {
+                    FnArg::Typed(pat_type) if is_context(arg) => match pat_type.ty.as_ref() {
+                        Type::Reference(reference) if reference.mutability.is_some() => reference,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let ty = reference.elem.to_token_stream().to_string();
+                match &context {
+                    Some(expected) if *expected != ty => errors.push(syn::Error::new_spanned(
+                        reference,
+                        "all `#[context]` parameters of a library have to be of the same type",
+                    )),
+                    Some(_) => (),
+                    None => context = Some(ty),
+                }
+            }
+        }
+        errors.finish()
+    }
     pub(crate) fn generate_enum(&self) -> TokenStream {
         let name = &self.name;
         let imports = self.imports.iter().map(|ident| quote! {#ident(#ident)});
@@ -62,12 +146,20 @@ impl MacroInput {
     pub(crate) fn generate_impl(&self) -> TokenStream {
         let name = &self.name;
 
-        let namespace = name.to_string().to_ascii_lowercase();
+        let namespace = attribute_values(&self.attrs, "namespace")
+            .next()
+            .unwrap_or_else(|| name.to_string().to_ascii_lowercase());
 
         let function_arg_count = self
             .functions
             .iter()
-            .map(|item_fn| item_fn.sig.inputs.len())
+            .map(|item_fn| {
+                let inputs = value_inputs(item_fn).count();
+                match item_fn.sig.inputs.last() {
+                    Some(arg) if is_rest(arg) => inputs - 1 + VARIADIC_INLINE_ARGS,
+                    _ => inputs,
+                }
+            })
             .max()
             .unwrap_or(0);
         let import_arg_count = self.imports.iter().map(|ident| {
@@ -80,7 +172,7 @@ impl MacroInput {
         });
         let max_args = quote! {
             {
-                let max_count = 0;
+                let mut max_count = 0;
                 #(#import_arg_count)*
                 if max_count > #function_arg_count {
                     max_count
@@ -92,68 +184,717 @@ impl MacroInput {
 
         let import_from_string = self.imports.iter().map(|ident| {
             quote! {
-                #ident::NAMESPACE => #ident::from_string(rest, identifier),
+                #ident::NAMESPACE => #ident::from_string(namespaces, identifier).map(Self::#ident),
             }
         });
         let function_from_string = self.functions.iter().map(|item_fn| {
-            let name = item_fn.sig.ident.to_string().to_ascii_lowercase();
+            let names = function_names(item_fn);
             let ident = &item_fn.sig.ident;
-            quote! { #name => Ok(Self::#ident),}
+            quote! { #(#names)|* => Ok(Self::#ident),}
         });
         let call = self.functions.iter().map(|item_fn| {
             let ident = &item_fn.sig.ident;
-            let args = item_fn
-                .sig
-                .inputs
+            let inputs = &item_fn.sig.inputs;
+            let mut count = 0usize;
+            let args = inputs
                 .iter()
-                .enumerate()
-                .map(|(count, arg)| match arg {
+                .filter_map(|arg| match arg {
+                    FnArg::Typed(_) if is_context(arg) => Some(quote! { context }),
+                    FnArg::Typed(_) if is_rest(arg) => Some(quote! { &args[#count..] }),
                     FnArg::Typed(pat_type) => {
                         let ty = &pat_type.ty;
-                        quote! { std::convert::TryInto::<#ty>::try_into(*args.get(#count).ok_or(Error::InvalidArg)?)?}
+                        count += 1;
+                        let count = count - 1;
+                        Some(match ty.as_ref() {
+                            Type::Reference(_) => quote! { std::convert::TryInto::<#ty>::try_into(&args[#count])? },
+                            _ => quote! { std::convert::TryInto::<#ty>::try_into(args[#count].clone())? },
+                        })
                     }
-                    _ => panic!("functions taking self are not allowed"),
-                });
+                    FnArg::Receiver(_) => None,
+                })
+                .collect::<Vec<_>>();
+            let result = wrap_result(item_fn, quote! { #ident(#(#args),*) });
+            let item_fn = strip_attributes(item_fn);
             quote! {
                 Self::#ident => {
                     #item_fn
-                    #ident(#(#args),*)
+                    let expected = self.arity();
+                    if !expected.contains(args.len()) {
+                        return Err(Error::InvalidArgCount { expected, found: args.len() });
+                    }
+                    #result
+                }
+            }
+        });
+        // spanned at the import, where a library with another context type fails to compile
+        let import_call = self.imports.iter().map(|ident| {
+            quote_spanned! {ident.span()=> Self::#ident(function) => function.call(context, args), }
+        });
+        let import_arity = self.imports.iter().map(|ident| {
+            quote! { Self::#ident(function) => function.arity(), }
+        });
+        let function_arity = self.functions.iter().map(|item_fn| {
+            let ident = &item_fn.sig.ident;
+            let arity = arity(item_fn);
+            quote! { Self::#ident => #arity, }
+        });
+        let import_signature = self.imports.iter().map(|ident| {
+            quote! { Self::#ident(function) => function.signature(), }
+        });
+        let function_signature = self.functions.iter().map(|item_fn| {
+            let ident = &item_fn.sig.ident;
+            let signature = signature(item_fn);
+            quote! { Self::#ident => #signature, }
+        });
+        let import_info = self.imports.iter().map(|ident| {
+            quote! { Self::#ident(function) => function.info().within(Self::NAMESPACE), }
+        });
+        let function_info = self.functions.iter().map(|item_fn| {
+            let ident = &item_fn.sig.ident;
+            let info = info(item_fn);
+            quote! { Self::#ident => #info, }
+        });
+        let function_idents = self.functions.iter().map(|item_fn| &item_fn.sig.ident);
+        let import_functions = self.imports.iter().map(|ident| {
+            quote! { .chain(#ident::functions().map(|info| info.within(Self::NAMESPACE))) }
+        });
+        let import_derivative = self.imports.iter().map(|ident| {
+            quote! { (Self::#ident(function), param) => function.derivative(param), }
+        });
+        let function_derivative = self.functions.iter().flat_map(|item_fn| {
+            let ident = &item_fn.sig.ident;
+            derivatives(item_fn)
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |(param, source)| quote! { (Self::#ident, #param) => Some(#source), })
+        });
+        let import_is_const = self.imports.iter().map(|ident| {
+            quote! { Self::#ident(function) => function.is_const(), }
+        });
+        let function_is_const = self.functions.iter().map(|item_fn| {
+            let ident = &item_fn.sig.ident;
+            let is_const = item_fn.sig.constness.is_some();
+            quote! { Self::#ident => #is_const, }
+        });
+        let own_context = self
+            .functions
+            .iter()
+            .flat_map(|item_fn| item_fn.sig.inputs.iter())
+            .find_map(|arg| match arg {
+                FnArg::Typed(pat_type) if is_context(arg) => match pat_type.ty.as_ref() {
+                    Type::Reference(reference) => Some(reference.elem.to_token_stream()),
+                    _ => None,
+                },
+                _ => None,
+            });
+        // without context parameters of its own, the library shares the one context of its imports
+        // which isn't `dyn Any`, and a differing one fails to compile at its import
+        let context =
+            own_context.unwrap_or_else(|| {
+                self.imports.iter().fold(quote! { dyn ::std::any::Any }, |context, ident| {
+                quote_spanned! {ident.span()=>
+                    <#context as SharedContext<<#ident as Library<#ident>>::Context>>::Shared
+                }
+            })
+            });
+        let import_constant = self.imports.iter().map(|ident| {
+            quote! {
+                #ident::NAMESPACE => #ident::constant(namespaces, identifier),
+            }
+        });
+        let constant = self.constants.iter().map(|constant| {
+            let name = constant.name.to_string();
+            let ty = &constant.ty;
+            let value = &constant.value;
+            quote! {
+                #name => {
+                    let value: #ty = #value;
+                    Some(Value::from(value))
                 }
             }
         });
         quote! {
-            impl Library<#name> for #name {
-                const NAMESPACE: &'static str = #namespace;
+            const _: () = {
+                /// Context of libraries using `Self` and `Other`, where `dyn Any` accepts any context.
+                pub trait SharedContext<Other: ?Sized> {
+                    type Shared: ?Sized;
+                }
+                impl<C: ?Sized> SharedContext<C> for C {
+                    type Shared = C;
+                }
+                impl<C> SharedContext<dyn ::std::any::Any> for C {
+                    type Shared = C;
+                }
+                impl<C> SharedContext<C> for dyn ::std::any::Any {
+                    type Shared = C;
+                }
+
+                #[allow(clippy::useless_conversion)]
+                impl Library<#name> for #name {
+                    const NAMESPACE: &'static str = #namespace;
+
+                    const MAX_ARGS: usize = #max_args;
 
-                const MAX_ARGS: usize = #max_args;
+                    type Context = #context;
 
-                fn from_string(namespaces: &[&str], identifier: &str) -> Result<#name, Error> {
-                    match namespaces {
-                        [namespace] => match namespace {
-                            &Self::NAMESPACE => Self::from_string(&[], identifier),
-                            _ => Err(Error::InvalidNamespace),
+                    fn from_string(namespaces: &[&str], identifier: &str) -> Result<#name, Error> {
+                        // the own namespace is optional, imports are passed the path starting with theirs
+                        let namespaces = match namespaces {
+                            [namespace, rest @ ..] if *namespace == Self::NAMESPACE => rest,
+                            _ => namespaces,
+                        };
+                        match namespaces {
+                            [namespace, ..] => match *namespace {
+                                #(#import_from_string)*
+                                _ => Err(Error::InvalidNamespace),
+                            }
+                            [] => match identifier {
+                                #(#function_from_string)*
+                                _ => Err(Error::UnknownFunction),
+                            }
                         }
-                        [namespace, rest @ ..] => match namespace {
-                            #(#import_from_string)*
-                            _ => Err(Error::InvalidNamespace),
+                    }
+
+                    fn constant(namespaces: &[&str], identifier: &str) -> Option<Value> {
+                        let namespaces = match namespaces {
+                            [namespace, rest @ ..] if *namespace == Self::NAMESPACE => rest,
+                            _ => namespaces,
+                        };
+                        match namespaces {
+                            [namespace, ..] => match *namespace {
+                                #(#import_constant)*
+                                _ => None,
+                            }
+                            [] => match identifier {
+                                #(#constant)*
+                                _ => None,
+                            }
                         }
-                        [] => match identifier {
-                            #(#function_from_string)*
-                            _ => Err(Error::UnknownFunction),
+                    }
+
+                    fn call(&self, context: &mut Self::Context, args: &[Value]) -> Result<Value, Error> {
+                        match self {
+                            #(#import_call)*
+                            #(#call),*
+                        }
+                    }
+
+                    fn arity(&self) -> Arity {
+                        match self {
+                            #(#import_arity)*
+                            #(#function_arity)*
+                        }
+                    }
+
+                    fn signature(&self) -> Signature {
+                        match self {
+                            #(#import_signature)*
+                            #(#function_signature)*
+                        }
+                    }
+
+                    fn info(&self) -> FunctionInfo {
+                        match self {
+                            #(#import_info)*
+                            #(#function_info)*
+                        }
+                    }
+
+                    fn functions() -> impl Iterator<Item = FunctionInfo> {
+                        let functions: Vec<FunctionInfo> = vec![#(Self::#function_idents.info()),*];
+                        functions.into_iter()#(#import_functions)*
+                    }
+
+                    fn derivative(&self, param: usize) -> Option<&'static str> {
+                        match (self, param) {
+                            #(#import_derivative)*
+                            #(#function_derivative)*
+                            _ => None,
                         }
                     }
-                }
 
-                fn call(&self, args: &[Value]) -> Result<Value, Error> {
-                    match self {
-                        #(#call),*
+                    fn is_const(&self) -> bool {
+                        match self {
+                            #(#import_is_const)*
+                            #(#function_is_const)*
+                        }
                     }
                 }
+            };
+        }
+    }
+}
+
+/// Number of arguments a rest parameter contributes to `MAX_ARGS`.
+/// Additional arguments are still accepted, but no longer stored inline.
+const VARIADIC_INLINE_ARGS: usize = 4;
 
-                fn is_const(&self) -> bool {
-                    todo!()
+/// Whether `arg` is marked with `#[rest]`, receiving all remaining arguments as `&[Value]`.
+fn is_rest(arg: &FnArg) -> bool {
+    match arg {
+        FnArg::Typed(pat_type) => pat_type.attrs.iter().any(|attr| attr.path.is_ident("rest")),
+        FnArg::Receiver(_) => false,
+    }
+}
+/// Generates the [`Arity`] of `item_fn`, which is unbounded if it takes a rest parameter.
+fn arity(item_fn: &ItemFn) -> TokenStream {
+    let count = value_inputs(item_fn).count();
+    match item_fn.sig.inputs.last() {
+        Some(arg) if is_rest(arg) => {
+            let min = count - 1;
+            quote! { Arity { min: #min, max: None } }
+        }
+        _ => quote! { Arity { min: #count, max: Some(#count) } },
+    }
+}
+/// Generates the `FunctionInfo` of `item_fn`, declared in the library's namespace.
+fn info(item_fn: &ItemFn) -> TokenStream {
+    let mut names = function_names(item_fn).into_iter();
+    let name = names.next();
+    let aliases = names;
+    let params = value_inputs(item_fn).map(|arg| match arg {
+        FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+            Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+            _ => String::from("_"),
+        },
+        FnArg::Receiver(_) => String::from("self"),
+    });
+    let arity = arity(item_fn);
+    let signature = signature(item_fn);
+    let docs = attribute_values(&item_fn.attrs, "doc")
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    quote! {
+        FunctionInfo {
+            namespaces: vec![Self::NAMESPACE],
+            name: ::std::borrow::Cow::Borrowed(#name),
+            aliases: &[#(#aliases),*],
+            params: &[#(#params),*],
+            arity: #arity,
+            signature: #signature,
+            docs: #docs,
+        }
+    }
+}
+/// Generates the [`Signature`] of `item_fn`.
+fn signature(item_fn: &ItemFn) -> TokenStream {
+    let mut params = Vec::new();
+    let mut rest = quote! { None };
+    for arg in value_inputs(item_fn) {
+        if let FnArg::Typed(pat_type) = arg {
+            if is_rest(arg) {
+                let ty = match pat_type.ty.as_ref() {
+                    Type::Reference(reference) => match reference.elem.as_ref() {
+                        Type::Slice(slice) => value_type(&slice.elem),
+                        _ => value_type(&reference.elem),
+                    },
+                    ty => value_type(ty),
+                };
+                rest = quote! { Some(#ty) };
+            } else {
+                params.push(value_type(&pat_type.ty));
+            }
+        }
+    }
+    let ret = match &item_fn.sig.output {
+        ReturnType::Type(_, ty) => value_type(ty),
+        ReturnType::Default => quote! { Type::Any },
+    };
+    quote! {
+        Signature {
+            params: &[#(#params),*],
+            rest: #rest,
+            ret: #ret,
+        }
+    }
+}
+/// Maps a Rust type to the `Type` of the values converted from or into it.
+fn value_type(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Slice(_) => quote! { Type::List },
+            ty => value_type(ty),
+        },
+        Type::Path(path) => {
+            let segment = match path.path.segments.last() {
+                Some(segment) => segment,
+                None => return quote! { Type::Any },
+            };
+            match segment.ident.to_string().as_str() {
+                "i64" | "bool" => quote! { Type::Int },
+                "f64" => quote! { Type::Float },
+                "Decimal" => quote! { Type::Decimal },
+                "Vec" => quote! { Type::List },
+                "Result" => match &segment.arguments {
+                    PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+                        Some(GenericArgument::Type(ty)) => value_type(ty),
+                        _ => quote! { Type::Any },
+                    },
+                    _ => quote! { Type::Any },
+                },
+                _ => quote! { Type::Any },
+            }
+        }
+        _ => quote! { Type::Any },
+    }
+}
+/// Values of all `#[key = "value"]` attributes in `attrs`.
+/// Malformed attributes are skipped, they are reported by [`MacroInput::validate`].
+fn attribute_values<'a>(attrs: &'a [Attribute], key: &'a str) -> impl Iterator<Item = String> + 'a {
+    attrs
+        .iter()
+        .filter(move |attr| attr.path.is_ident(key))
+        .filter_map(|attr| string_value(attr).ok())
+}
+/// Value of an attribute of the form `#[key = "value"]`.
+fn string_value(attr: &Attribute) -> syn::Result<String> {
+    string_literal(attr).map(|value| value.value())
+}
+/// Literal of an attribute of the form `#[key = "value"]`, to report errors about the value at.
+fn string_literal(attr: &Attribute) -> syn::Result<LitStr> {
+    match attr.parse_meta()? {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(value),
+            ..
+        }) => Ok(value),
+        meta => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `#[{} = \"...\"]`", attr.path.to_token_stream()),
+        )),
+    }
+}
+/// Value of an attribute naming a `kind` of "function" or "namespace", which has to be parsed as one
+/// in expressions: a letter or `_`, followed by letters, digits, `_` and, in function names only, `.`.
+fn name_value(attr: &Attribute, kind: &str) -> syn::Result<String> {
+    let value = string_literal(attr)?;
+    let name = value.value();
+    let mut chars = name.chars();
+    let is_name = chars
+        .next()
+        .is_some_and(|chr| chr.is_ascii_alphabetic() || chr == '_')
+        && chars.all(|chr| {
+            chr.is_ascii_alphanumeric() || chr == '_' || (kind == "function" && chr == '.')
+        });
+    if is_name {
+        return Ok(name);
+    }
+    let characters = match kind {
+        "function" => "letters, digits, `_` or `.`",
+        _ => "letters, digits or `_`",
+    };
+    Err(syn::Error::new_spanned(
+        value,
+        format!("`{name}` isn't a valid {kind} name, which starts with a letter or `_` followed by {characters}"),
+    ))
+}
+/// Errors collected while validating the input, reported together.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+impl Errors {
+    fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+    fn check<T>(&mut self, result: syn::Result<T>) {
+        if let Err(error) = result {
+            self.push(error);
+        }
+    }
+    fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+/// Checks the signature of a library function: its parameters have to be convertible from a `Value`,
+/// a `#[rest]` parameter has to come last and a `#[context]` parameter has to be a mutable reference.
+fn validate_function(item_fn: &ItemFn) -> syn::Result<()> {
+    let mut errors = Errors::default();
+    let sig = &item_fn.sig;
+    if !sig.generics.params.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &sig.generics,
+            "library functions can't be generic",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        errors.push(syn::Error::new_spanned(
+            asyncness,
+            "library functions can't be async",
+        ));
+    }
+    for (position, arg) in sig.inputs.iter().enumerate() {
+        let pat_type = match arg {
+            FnArg::Typed(pat_type) => pat_type,
+            FnArg::Receiver(receiver) => {
+                errors.push(syn::Error::new_spanned(
+                    receiver,
+                    "library functions can't take `self`",
+                ));
+                continue;
+            }
+        };
+        let ty = pat_type.ty.as_ref();
+        if is_context(arg) {
+            if is_rest(arg) {
+                errors.push(syn::Error::new_spanned(
+                    arg,
+                    "a parameter can't be both `#[context]` and `#[rest]`",
+                ));
+            }
+            if !matches!(ty, Type::Reference(reference) if reference.mutability.is_some()) {
+                errors.push(syn::Error::new_spanned(
+                    ty,
+                    "`#[context]` parameters have to be mutable references, such as `&mut Host`",
+                ));
+            }
+        } else if is_rest(arg) {
+            if position + 1 != sig.inputs.len() {
+                errors.push(syn::Error::new_spanned(
+                    arg,
+                    "only the last parameter can be `#[rest]`",
+                ));
+            }
+            if !is_value_slice(ty) {
+                errors.push(syn::Error::new_spanned(
+                    ty,
+                    "`#[rest]` parameters have to be `&[Value]`",
+                ));
+            }
+        } else if !is_param_type(ty) {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "unsupported parameter type `{}`, expected `i64`, `f64`, `Decimal`, `Value`, `Vec<Value>`, `&Value` or `&[Value]`",
+                    ty.to_token_stream()
+                ),
+            ));
+        }
+    }
+    errors.check(derivatives(item_fn));
+    if !is_return_type(&sig.output) {
+        let message =
+            "library functions have to return `i64`, `f64`, `bool`, `Decimal`, `Value`, `Vec<Value>` or a `Result` of them";
+        errors.push(match &sig.output {
+            ReturnType::Type(_, ty) => syn::Error::new_spanned(ty, message),
+            ReturnType::Default => syn::Error::new_spanned(sig, message),
+        });
+    }
+    errors.finish()
+}
+/// Whether arguments can be converted into `ty` with `TryInto`.
+fn is_param_type(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            is_value_slice(ty) || is_path(&reference.elem, "Value")
+        }
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => match segment.ident.to_string().as_str() {
+                "i64" | "f64" | "Decimal" | "Value" => segment.arguments.is_empty(),
+                "Vec" => {
+                    generic_argument(&segment.arguments).is_some_and(|ty| is_path(ty, "Value"))
+                }
+                _ => false,
+            },
+            None => false,
+        },
+        _ => false,
+    }
+}
+/// Whether `ty` is `&[Value]`.
+fn is_value_slice(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            matches!(reference.elem.as_ref(), Type::Slice(slice) if is_path(&slice.elem, "Value"))
+        }
+        _ => false,
+    }
+}
+/// Whether `output` can be converted into `Result<Value, Error>`:
+/// `i64`, `f64`, `bool`, `Decimal`, `Value`, `Vec<Value>` or a `Result` of them.
+fn is_return_type(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => match path.path.segments.last() {
+                Some(segment) if segment.ident == "Result" => {
+                    generic_argument(&segment.arguments).is_some_and(is_value_type)
+                }
+                _ => is_value_type(ty),
+            },
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+/// Whether `ty` can be converted into `Value`:
+/// `i64`, `f64`, `bool`, `Decimal`, `Value` or `Vec<Value>`.
+fn is_value_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => match segment.ident.to_string().as_str() {
+                "i64" | "f64" | "bool" | "Decimal" | "Value" => segment.arguments.is_empty(),
+                "Vec" => {
+                    generic_argument(&segment.arguments).is_some_and(|ty| is_path(ty, "Value"))
+                }
+                _ => false,
+            },
+            None => false,
+        },
+        _ => false,
+    }
+}
+/// Whether `ty` is a `Result`, whose value and error are converted with `From`.
+fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Result"),
+        _ => false,
+    }
+}
+/// Converts the value returned by `call` into `Result<Value, Error>`.
+fn wrap_result(item_fn: &ItemFn, call: TokenStream) -> TokenStream {
+    match &item_fn.sig.output {
+        ReturnType::Type(_, ty) if is_result(ty) => quote_spanned! { ty.span() =>
+            match #call {
+                Ok(value) => Ok(Value::from(value)),
+                Err(error) => Err(Error::from(error)),
+            }
+        },
+        ReturnType::Type(_, ty) => quote_spanned! { ty.span() => Ok(Value::from(#call)) },
+        ReturnType::Default => call,
+    }
+}
+/// Whether `ty` is a path ending in `name`, without generic arguments.
+fn is_path(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name && segment.arguments.is_empty()),
+        _ => false,
+    }
+}
+/// First generic type argument, such as `T` in `Vec<T>`.
+fn generic_argument(arguments: &PathArguments) -> Option<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+/// Names `item_fn` is exposed as: its lowercase identifier or `#[name = "..."]`,
+/// followed by every `#[alias = "..."]`.
+fn function_names(item_fn: &ItemFn) -> Vec<String> {
+    let name = attribute_values(&item_fn.attrs, "name")
+        .next()
+        .unwrap_or_else(|| item_fn.sig.ident.to_string().to_ascii_lowercase());
+    std::iter::once(name)
+        .chain(attribute_values(&item_fn.attrs, "alias"))
+        .collect()
+}
+/// Partial derivatives declared by `#[derivative = "..."]` for functions of one parameter,
+/// or by `#[derivative(param = "...", ...)]`, with the position of their parameter.
+fn derivatives(item_fn: &ItemFn) -> syn::Result<Vec<(usize, String)>> {
+    let params = value_inputs(item_fn)
+        .map(|arg| match arg {
+            FnArg::Typed(pat_type) => pat_type.pat.to_token_stream().to_string(),
+            FnArg::Receiver(_) => String::from("self"),
+        })
+        .collect::<Vec<_>>();
+    let mut derivatives = Vec::new();
+    for attr in item_fn
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derivative"))
+    {
+        match attr.parse_meta()? {
+            Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(source),
+                ..
+            }) if params.len() == 1 => derivatives.push((0, source.value())),
+            Meta::NameValue(meta) => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "`#[derivative = \"...\"]` requires a function of one parameter, \
+                     use `#[derivative(param = \"...\")]` instead",
+                ))
+            }
+            Meta::List(list) => {
+                for nested in list.nested {
+                    let (param, source) = match &nested {
+                        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                            path,
+                            lit: Lit::Str(source),
+                            ..
+                        })) => (path.to_token_stream().to_string(), source.value()),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                "expected `param = \"...\"`",
+                            ))
+                        }
+                    };
+                    match params.iter().position(|name| *name == param) {
+                        Some(position) => derivatives.push((position, source)),
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                format!("no parameter named `{param}`"),
+                            ))
+                        }
+                    }
                 }
             }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[derivative = \"...\"]` or `#[derivative(param = \"...\")]`",
+                ))
+            }
+        }
+    }
+    Ok(derivatives)
+}
+/// Whether `arg` is marked with `#[context]`, receiving the context passed to `Library::call`.
+fn is_context(arg: &FnArg) -> bool {
+    match arg {
+        FnArg::Typed(pat_type) => pat_type
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("context")),
+        FnArg::Receiver(_) => false,
+    }
+}
+/// Parameters of `item_fn` which receive arguments of the function call.
+fn value_inputs(item_fn: &ItemFn) -> impl Iterator<Item = &FnArg> {
+    item_fn.sig.inputs.iter().filter(|arg| !is_context(arg))
+}
+/// Removes the attributes and `const` marker consumed by `library!`,
+/// so the function can be emitted as a regular function.
+fn strip_attributes(item_fn: &ItemFn) -> ItemFn {
+    let mut item_fn = item_fn.clone();
+    item_fn.sig.constness = None;
+    item_fn.attrs.retain(|attr| {
+        !attr.path.is_ident("name")
+            && !attr.path.is_ident("alias")
+            && !attr.path.is_ident("derivative")
+    });
+    for arg in item_fn.sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = arg {
+            pat_type
+                .attrs
+                .retain(|attr| !attr.path.is_ident("rest") && !attr.path.is_ident("context"));
         }
     }
+    item_fn
 }
@@ -1,3 +1,4 @@
+use crate::{library::Arity, value::Type};
 use std::{
     array::TryFromSliceError,
     convert::Infallible,
@@ -13,15 +14,35 @@ pub enum Error {
     InvalidToken,
     InvalidNamespace,
     InvalidArg,
+    InvalidArgCount {
+        expected: Arity,
+        found: usize,
+    },
     InvalidIndex,
     InvalidVariable,
     InvalidType,
+    TypeMismatch {
+        /// Position in the expression string
+        position: usize,
+        expected: Type,
+        found: Type,
+    },
     NotCompiled,
     UnbalancedBracket,
 
     AlreadyCompiled,
     EmptyExpression,
     UnknownFunction,
+    /// Name found in more than one default namespace
+    AmbiguousIdentifier(String),
+    /// Function or operator without a known derivative
+    NotDifferentiable(String),
+    /// Integer division or remainder by zero
+    DivisionByZero,
+    /// Integer result which doesn't fit in an `i64`, with [`Arithmetic::Checked`](crate::value::Arithmetic::Checked)
+    Overflow,
+    /// Integer power with a negative exponent, whose result isn't an integer
+    NegativeExponent,
 }
 
 impl From<ParseFloatError> for Error {
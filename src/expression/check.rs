@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use super::{Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::{Coercion, Type},
+};
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Infers the type of the parsed expression, without evaluating it.
+    /// Variables and functions returning [`Value`](crate::value::Value) are of [`Type::Any`],
+    /// which is accepted everywhere.
+    pub fn check(&self) -> Result<Type, Error> {
+        match self.root {
+            Some(index) => self.storage.check(index, self.coercion),
+            None => Err(Error::NotCompiled),
+        }
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    pub(crate) fn check(&self, index: ElementIndex, coercion: Coercion) -> Result<Type, Error> {
+        self.check_memoised(index, coercion, &mut HashMap::new())
+    }
+    /// Checks the element at `index`, reusing the types recorded in `types` and recording new ones,
+    /// where `None` records an element which failed to check.
+    /// Recorded types stay valid as long as the subtrees they were recorded for aren't changed.
+    pub(crate) fn check_memoised(
+        &self,
+        index: ElementIndex,
+        coercion: Coercion,
+        types: &mut HashMap<ElementIndex, Option<Type>>,
+    ) -> Result<Type, Error> {
+        match types.get(&index) {
+            Some(Some(found)) => return Ok(*found),
+            Some(None) => return Err(Error::InvalidType),
+            None => (),
+        }
+        let found = self.check_element(index, coercion, types);
+        types.insert(index, found.as_ref().ok().copied());
+        found
+    }
+    fn check_element(
+        &self,
+        index: ElementIndex,
+        coercion: Coercion,
+        types: &mut HashMap<ElementIndex, Option<Type>>,
+    ) -> Result<Type, Error> {
+        Ok(match &self.elements[index] {
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                let lhs = self.check_operand(*lhs, Type::Number, coercion, types)?;
+                let rhs = self.check_operand(*rhs, Type::Number, coercion, types)?;
+                operator.result_type(lhs, rhs)
+            }
+            Node::Literal(value) => value.value_type(),
+            Node::Variable(_) => Type::Any,
+            Node::Function(Function { function, args }) => {
+                let signature = function.signature();
+                for (count, arg) in args.iter().enumerate() {
+                    let expected = signature.param(count).ok_or(Error::InvalidArg)?;
+                    self.check_operand(*arg, expected, coercion, types)?;
+                }
+                signature.ret
+            }
+            Node::List(List { items }) => {
+                for item in items {
+                    self.check_memoised(*item, coercion, types)?;
+                }
+                Type::List
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                self.check_operand(*list, Type::List, coercion, types)?;
+                self.check_operand(*index, Type::Int, coercion, types)?;
+                Type::Any
+            }
+        })
+    }
+    /// Checks the element at `index` and whether its type is accepted as `expected`.
+    fn check_operand(
+        &self,
+        index: ElementIndex,
+        expected: Type,
+        coercion: Coercion,
+        types: &mut HashMap<ElementIndex, Option<Type>>,
+    ) -> Result<Type, Error> {
+        let found = self.check_memoised(index, coercion, types)?;
+        if coercion.accepts(expected, found) {
+            Ok(found)
+        } else {
+            Err(Error::TypeMismatch {
+                position: self.elements.position(index),
+                expected,
+                found,
+            })
+        }
+    }
+}
+impl Operator {
+    fn result_type(&self, lhs: Type, rhs: Type) -> Type {
+        match (lhs, rhs) {
+            (Type::Int, Type::Int) => Type::Int,
+            (Type::Float, _) | (_, Type::Float) => Type::Float,
+            #[cfg(feature = "decimal")]
+            (Type::Decimal, Type::Int | Type::Decimal) | (Type::Int, Type::Decimal) => {
+                Type::Decimal
+            }
+            _ => Type::Number,
+        }
+    }
+}
+#[test]
+fn check() {
+    for (source, result) in [
+        ("1+2*3", Type::Int),
+        ("1+x", Type::Number),
+        ("len([1,2])", Type::Int),
+        ("sqrt(2)*2", Type::Float),
+        ("abs(1)", Type::Any),
+        ("[1,2][x]", Type::Any),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert_eq!(expression.check().unwrap(), result, "{source}");
+    }
+    for (source, position, expected, found) in [
+        ("1+[2]", 2, Type::Number, Type::List),
+        ("len(1)", 4, Type::List, Type::Int),
+        ("sum(1)+[1,2][[0]]", 13, Type::Int, Type::List),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert!(
+            matches!(
+                expression.check(),
+                Err(Error::TypeMismatch { position: p, expected: e, found: f })
+                    if p == position && e == expected && f == found
+            ),
+            "{source}"
+        );
+    }
+}
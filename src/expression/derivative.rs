@@ -0,0 +1,358 @@
+use super::{scope::Scope, Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::{Arithmetic, Coercion, Value},
+};
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+impl<T> Expression<T>
+where
+    T: Library<T> + Clone,
+    [(); T::MAX_ARGS]:,
+{
+    /// Partial derivative of the parsed expression with respect to the variable `variable`,
+    /// using the derivatives declared by the library for its functions.
+    /// The result is simplified by folding its constants, without calling any functions,
+    /// and keeps the values of the variables set on this expression.
+    /// Its elements don't refer to positions in its string, which is printed from the derived tree.
+    pub fn derivative(&self, variable: &str) -> Result<Expression<T>, Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let mut derivative = Expression::<T> {
+            coercion: self.coercion,
+            arithmetic: self.arithmetic,
+            ..Default::default()
+        };
+        derivative.storage.scope = self.storage.scope.clone();
+        let storage = &mut derivative.storage;
+        let root = match storage.derive(&self.storage, root, variable)? {
+            Some(root) => root,
+            None => storage.elements.push_node(Value::Int(0), 0),
+        };
+        storage.fold(root, derivative.coercion, derivative.arithmetic, None);
+        derivative.root = Some(root);
+        derivative.string = derivative.to_string();
+        Ok(derivative)
+    }
+}
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Partial derivative of `function` with respect to the parameter at `param`, parsed from its declaration.
+    /// Its variables are the parameters of `function`.
+    pub(super) fn partial(function: &T, param: usize) -> Result<Self, Error> {
+        let info = function.info();
+        let source = function
+            .derivative(param)
+            .ok_or_else(|| Error::NotDifferentiable(info.qualified_name()))?;
+        let mut scope = Scope::default();
+        scope.use_namespace(&info.namespaces.join("::"));
+        let mut derivative = Expression::<T>::new(String::from(source));
+        derivative.set_scope(scope);
+        derivative.parse()?;
+        Ok(derivative)
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T> + Clone,
+    [(); T::MAX_ARGS]:,
+{
+    /// Pushes the derivative of the element at `index` of `source` with respect to `variable`,
+    /// or returns `None` if the element doesn't depend on `variable`.
+    fn derive(
+        &mut self,
+        source: &ExpressionStorage<T>,
+        index: ElementIndex,
+        variable: &str,
+    ) -> Result<Option<ElementIndex>, Error> {
+        Ok(match &source.elements[index] {
+            Node::Literal(_) => None,
+            Node::Variable(other) => (source.variables.name(*other) == variable)
+                .then(|| self.elements.push_node(Value::Int(1), 0)),
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                let du = self.derive(source, *lhs, variable)?;
+                let dv = self.derive(source, *rhs, variable)?;
+                if du.is_none() && dv.is_none() {
+                    return Ok(None);
+                }
+                let u = self.copy_from(source, *lhs, &[]);
+                let v = self.copy_from(source, *rhs, &[]);
+                match operator {
+                    Operator::Add => self.add(du, dv),
+                    Operator::Sub => self.sub(du, dv),
+                    // (u * v)' = u' * v + u * v'
+                    Operator::Mul => {
+                        let du = du.map(|du| self.mul(du, v));
+                        let dv = dv.map(|dv| self.mul(u, dv));
+                        self.add(du, dv)
+                    }
+                    // (u / v)' = (u' * v - u * v') / v ^ 2, with a float exponent
+                    // so that integer operands aren't truncated by integer division
+                    Operator::Div => {
+                        let du = du.map(|du| self.mul(du, v));
+                        let dv = dv.map(|dv| self.mul(u, dv));
+                        let numerator = self.sub(du, dv);
+                        let two = self.elements.push_node(Value::Float(2.0), 0);
+                        let denominator = self.instruction(Operator::Pow, v, two);
+                        numerator.map(|numerator| {
+                            self.instruction(Operator::Div, numerator, denominator)
+                        })
+                    }
+                    // (u ^ c)' = c * u ^ (c - 1) * u'
+                    Operator::Pow if dv.is_none() => {
+                        let one = self.elements.push_node(Value::Int(1), 0);
+                        let exponent = self.instruction(Operator::Sub, v, one);
+                        let power = match self.fold(
+                            exponent,
+                            Coercion::default(),
+                            Arithmetic::default(),
+                            None,
+                        ) {
+                            Some(Value::Int(1)) => u,
+                            _ => self.instruction(Operator::Pow, u, exponent),
+                        };
+                        let factor = self.mul(v, power);
+                        du.map(|du| self.mul(factor, du))
+                    }
+                    // (u ^ v)' = u ^ v * (v' * ln(u) + v * u' / u)
+                    Operator::Pow => {
+                        let ln = self.call("ln", u)?;
+                        let dv = dv.map(|dv| self.mul(dv, ln));
+                        let du = du.map(|du| {
+                            let du = self.mul(v, du);
+                            self.instruction(Operator::Div, du, u)
+                        });
+                        let power = self.instruction(Operator::Pow, u, v);
+                        self.add(dv, du).map(|sum| self.mul(power, sum))
+                    }
+                    // (u % c)' = u'
+                    Operator::Rem if dv.is_none() => du,
+                    operator => return Err(Error::NotDifferentiable(operator.to_string())),
+                }
+            }
+            Node::Function(Function { function, args }) => {
+                let mut derivative = None;
+                let mut copies = Vec::with_capacity(args.len());
+                for arg in args {
+                    copies.push(self.copy_from(source, *arg, &[]));
+                }
+                for (param, arg) in args.iter().enumerate() {
+                    let Some(darg) = self.derive(source, *arg, variable)? else {
+                        continue;
+                    };
+                    let partial = self.partial(function, param, &copies)?;
+                    let term = self.mul(partial, darg);
+                    derivative = self.add(derivative, Some(term));
+                }
+                derivative
+            }
+            Node::List(List { items }) => {
+                let mut derivatives = Vec::with_capacity(items.len());
+                for item in items {
+                    derivatives.push(self.derive(source, *item, variable)?);
+                }
+                if derivatives.iter().all(Option::is_none) {
+                    return Ok(None);
+                }
+                let items = derivatives
+                    .into_iter()
+                    .map(|item| item.unwrap_or_else(|| self.elements.push_node(Value::Int(0), 0)))
+                    .collect();
+                Some(self.elements.push_node(List { items }, 0))
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                match self.derive(source, *list, variable)? {
+                    Some(list) => {
+                        let index = self.copy_from(source, *index, &[]);
+                        Some(self.elements.push_node(Subscript { list, index }, 0))
+                    }
+                    None => None,
+                }
+            }
+        })
+    }
+    /// Pushes the partial derivative of `function` with respect to the parameter at `param`,
+    /// with its parameters replaced by `args`.
+    fn partial(
+        &mut self,
+        function: &T,
+        param: usize,
+        args: &[ElementIndex],
+    ) -> Result<ElementIndex, Error> {
+        let derivative = Expression::partial(function, param)?;
+        let info = function.info();
+        let substitutions = info
+            .params
+            .iter()
+            .copied()
+            .zip(args.iter().copied())
+            .collect::<Vec<_>>();
+        Ok(self.copy_from(
+            &derivative.storage,
+            derivative.root.unwrap(),
+            &substitutions,
+        ))
+    }
+    /// Pushes a call of the single argument function named `name` of the library.
+    fn call(&mut self, name: &str, arg: ElementIndex) -> Result<ElementIndex, Error> {
+        let function = T::functions()
+            .find(|info| info.name == name)
+            .ok_or(Error::UnknownFunction)
+            .and_then(|info| T::from_qualified_name(&info.qualified_name()))
+            .map_err(|_| Error::NotDifferentiable(format!("^ without `{name}`")))?;
+        let args = [arg].into_iter().collect();
+        Ok(self.elements.push_node(Function { function, args }, 0))
+    }
+    /// Copies the element at `index` of `source` and its operands,
+    /// replacing variables named in `substitutions` by the given elements.
+    pub(crate) fn copy_from(
+        &mut self,
+        source: &ExpressionStorage<T>,
+        index: ElementIndex,
+        substitutions: &[(&str, ElementIndex)],
+    ) -> ElementIndex {
+        let node = match &source.elements[index] {
+            Node::Literal(value) => Node::Literal(value.clone()),
+            Node::Variable(variable) => {
+                let name = source.variables.name(*variable);
+                if let Some((_, substitute)) =
+                    substitutions.iter().find(|(param, _)| *param == name)
+                {
+                    return *substitute;
+                }
+                let copy = self.variables.find_or_set(name);
+                self.variables
+                    .set(name, source.variables[*variable].clone())
+                    .unwrap();
+                Node::Variable(copy)
+            }
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                let lhs = self.copy_from(source, *lhs, substitutions);
+                let rhs = self.copy_from(source, *rhs, substitutions);
+                Node::Instruction(Instruction {
+                    operator: *operator,
+                    lhs,
+                    rhs,
+                })
+            }
+            Node::Function(Function { function, args }) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.copy_from(source, *arg, substitutions))
+                    .collect();
+                Node::Function(Function {
+                    function: function.clone(),
+                    args,
+                })
+            }
+            Node::List(List { items }) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.copy_from(source, *item, substitutions))
+                    .collect();
+                Node::List(List { items })
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                let list = self.copy_from(source, *list, substitutions);
+                let index = self.copy_from(source, *index, substitutions);
+                Node::Subscript(Subscript { list, index })
+            }
+        };
+        let position = source.elements.position(index);
+        self.elements.push_node(node, position)
+    }
+    fn instruction(
+        &mut self,
+        operator: Operator,
+        lhs: ElementIndex,
+        rhs: ElementIndex,
+    ) -> ElementIndex {
+        self.elements
+            .push_node(Instruction { operator, lhs, rhs }, 0)
+    }
+    /// Sum of two derivatives, where `None` is zero.
+    fn add(
+        &mut self,
+        lhs: Option<ElementIndex>,
+        rhs: Option<ElementIndex>,
+    ) -> Option<ElementIndex> {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(self.instruction(Operator::Add, lhs, rhs)),
+            (lhs, rhs) => lhs.or(rhs),
+        }
+    }
+    /// Difference of two derivatives, where `None` is zero.
+    fn sub(
+        &mut self,
+        lhs: Option<ElementIndex>,
+        rhs: Option<ElementIndex>,
+    ) -> Option<ElementIndex> {
+        match (lhs, rhs) {
+            (lhs, None) => lhs,
+            (lhs, Some(rhs)) => {
+                let lhs = lhs.unwrap_or_else(|| self.elements.push_node(Value::Int(0), 0));
+                Some(self.instruction(Operator::Sub, lhs, rhs))
+            }
+        }
+    }
+    /// Product which leaves out factors of one.
+    fn mul(&mut self, lhs: ElementIndex, rhs: ElementIndex) -> ElementIndex {
+        let one = |index| matches!(self.elements[index], Node::Literal(Value::Int(1)));
+        match (one(lhs), one(rhs)) {
+            (true, _) => rhs,
+            (_, true) => lhs,
+            _ => self.instruction(Operator::Mul, lhs, rhs),
+        }
+    }
+}
+#[test]
+fn derivative() {
+    for (source, derivative) in [
+        ("x^3+2*x", "3 * x ^ 2 + 2"),
+        ("x*y-y", "y"),
+        ("x/y", "y / y ^ 2.0"),
+        ("sin(x)*x", "std::cos(x) * x + std::sin(x)"),
+        ("sqrt(x^2+1)", "1 / (2 * std::sqrt(x ^ 2 + 1)) * (2 * x)"),
+        ("2^x", "2 ^ x * std::ln(2)"),
+        ("hypot(x, 3)", "x / std::hypot(x, 3)"),
+        ("y+pi", "0"),
+        ("[x, 1][0]", "1"),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        let derivative_expression = expression.derivative("x").unwrap();
+        assert_eq!(derivative_expression.to_string(), derivative, "{source}");
+    }
+
+    let mut expression = Expression::<Std>::new(String::from("x^2+ln(x)"));
+    expression.parse().unwrap();
+    expression.set_variable("x", 2).unwrap();
+    let derivative = expression.derivative("x").unwrap();
+    assert_eq!(derivative.eval().unwrap(), Value::Float(4.5));
+
+    for (source, derivative) in [
+        ("x/2", Value::Float(0.5)),
+        ("1/x", Value::Float(-1.0 / 9.0)),
+        ("x/y", Value::Float(0.5)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        expression.set_variable("x", 3).ok();
+        expression.set_variable("y", 2).ok();
+        let slope = expression.derivative("x").unwrap().eval().unwrap();
+        assert_eq!(slope, derivative, "{source}");
+    }
+
+    let mut expression = Expression::<Std>::new(String::from("print(x)"));
+    expression.parse().unwrap();
+    assert!(matches!(
+        expression.derivative("x"),
+        Err(Error::NotDifferentiable(name)) if name == "std::print"
+    ));
+}
@@ -0,0 +1,245 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{Expression, ExpressionStorage};
+use crate::{
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::Value,
+};
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+/// Prints the parsed expression as canonical source, with fully qualified function names
+/// and only the parentheses required by operator precedence.
+/// Expressions which haven't been parsed are printed as they were written.
+impl<T> Display for Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.root {
+            Some(index) => self.storage.fmt_node(f, index),
+            None => f.write_str(&self.string),
+        }
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn fmt_node(&self, f: &mut Formatter<'_>, index: ElementIndex) -> fmt::Result {
+        match &self.elements[index] {
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                let lhs_grouped = self
+                    .operator(*lhs)
+                    .is_some_and(|lhs| operator.weight() > lhs.rhs_weight());
+                let rhs_grouped = self
+                    .operator(*rhs)
+                    .is_some_and(|rhs| rhs.weight() <= operator.rhs_weight());
+                self.fmt_grouped(f, *lhs, lhs_grouped)?;
+                write!(f, " {operator} ")?;
+                self.fmt_grouped(f, *rhs, rhs_grouped)
+            }
+            Node::Literal(value) => fmt_literal(f, value),
+            Node::Variable(index) => f.write_str(self.variables.name(*index)),
+            Node::Function(Function { function, args }) => {
+                write!(f, "{}(", function.info().qualified_name())?;
+                self.fmt_items(f, args)?;
+                f.write_str(")")
+            }
+            Node::List(List { items }) => {
+                f.write_str("[")?;
+                self.fmt_items(f, items)?;
+                f.write_str("]")
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                let grouped = matches!(self.elements[*list], Node::Instruction(_));
+                self.fmt_grouped(f, *list, grouped)?;
+                f.write_str("[")?;
+                self.fmt_node(f, *index)?;
+                f.write_str("]")
+            }
+        }
+    }
+    fn fmt_grouped(
+        &self,
+        f: &mut Formatter<'_>,
+        index: ElementIndex,
+        grouped: bool,
+    ) -> fmt::Result {
+        if grouped {
+            f.write_str("(")?;
+            self.fmt_node(f, index)?;
+            f.write_str(")")
+        } else {
+            self.fmt_node(f, index)
+        }
+    }
+    fn fmt_items(&self, f: &mut Formatter<'_>, items: &[ElementIndex]) -> fmt::Result {
+        for (count, item) in items.iter().enumerate() {
+            if count > 0 {
+                f.write_str(", ")?;
+            }
+            self.fmt_node(f, *item)?;
+        }
+        Ok(())
+    }
+    fn operator(&self, index: ElementIndex) -> Option<Operator> {
+        match &self.elements[index] {
+            Node::Instruction(Instruction { operator, .. }) => Some(*operator),
+            _ => None,
+        }
+    }
+}
+impl Operator {
+    /// Weight its right operand is parsed with, only operators binding stronger become part of it.
+    fn rhs_weight(&self) -> i16 {
+        match self {
+            // right associative
+            Self::Pow => self.weight() - 1,
+            _ => self.weight(),
+        }
+    }
+}
+/// Prints a literal so that it is parsed as the same value,
+/// keeping the decimal point of floats and wrapping negative numbers as a subtraction.
+fn fmt_literal(f: &mut Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        // the magnitude of `i64::MIN` isn't a valid literal
+        Value::Int(i64::MIN) => write!(f, "(0 - {} - 1)", i64::MAX),
+        Value::Int(int) if *int < 0 => write!(f, "(0 - {})", int.unsigned_abs()),
+        // infinities are printed as the literal `inf`, and NaN as the constant `nan` of the std library
+        Value::Float(float) if float.is_nan() => f.write_str("nan"),
+        // `0 - 0.0` is positive, while a negative factor keeps the sign of the zero
+        Value::Float(float) if *float == 0.0 && float.is_sign_negative() => {
+            f.write_str("((0 - 1) * 0.0)")
+        }
+        Value::Float(float) if float.is_sign_negative() && *float != 0.0 => {
+            write!(f, "(0 - ")?;
+            fmt_literal(f, &Value::Float(-float))?;
+            f.write_str(")")
+        }
+        #[cfg(feature = "decimal")]
+        Value::Decimal(decimal) if decimal.is_sign_negative() && !decimal.is_zero() => {
+            write!(f, "(0 - ")?;
+            fmt_literal(f, &Value::Decimal(-decimal))?;
+            f.write_str(")")
+        }
+        // negative zero is printed as zero,
+        // and integral decimals with a fraction so that they aren't parsed as integers
+        #[cfg(feature = "decimal")]
+        Value::Decimal(decimal) if decimal.scale() == 0 => write!(f, "{}.0", decimal.abs()),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(decimal) => write!(f, "{}", decimal.abs()),
+        Value::Float(float) if float.is_finite() && float.fract() == 0.0 => write!(f, "{float:.1}"),
+        Value::List(list) => {
+            f.write_str("[")?;
+            for (count, item) in list.iter().enumerate() {
+                if count > 0 {
+                    f.write_str(", ")?;
+                }
+                fmt_literal(f, item)?;
+            }
+            f.write_str("]")
+        }
+        value => write!(f, "{value}"),
+    }
+}
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Pow => "^",
+        };
+        f.write_str(symbol)
+    }
+}
+#[test]
+fn display() {
+    for (source, printed) in [
+        ("1+2*3", "1 + 2 * 3"),
+        ("(1+2)*3", "(1 + 2) * 3"),
+        ("1-(2-3)-4", "1 - (2 - 3) - 4"),
+        ("((1*2))/(3/x)", "1 * 2 / (3 / x)"),
+        ("2^3^2", "2 ^ 3 ^ 2"),
+        ("(2^3)^2", "(2 ^ 3) ^ 2"),
+        ("2%3^4", "2 % 3 ^ 4"),
+        ("2^(3%4)", "2 ^ 3 % 4"),
+        ("2^3%4", "2 ^ 3 % 4"),
+        (
+            "sqrt(x)+max(1,[2,3][0])",
+            "std::sqrt(x) + std::max(1, [2, 3][0])",
+        ),
+        ("(xs+ys)[i]+sum()", "(xs + ys)[i] + std::sum()"),
+        ("min(inf,1)", "std::min(inf, 1)"),
+        ("nan+inf", "nan + inf"),
+        ("1.5e3+.25", "1500.0 + 0.25"),
+        ("0x1F-1_000", "31 - 1000"),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        let string = expression.to_string();
+        assert_eq!(string, printed, "{source}");
+
+        let mut reparsed = Expression::<Std>::new(string);
+        reparsed.parse().unwrap();
+        assert_eq!(reparsed.to_string(), printed, "{source}");
+    }
+    assert_eq!(
+        Expression::<Std>::new(String::from("1 + 2")).to_string(),
+        "1+2"
+    );
+
+    for (source, printed) in [
+        ("0-inf", "(0 - inf)"),
+        ("0.0/0", "nan"),
+        ("1-nan", "nan"),
+        ("0.0*(0-1)", "((0 - 1) * 0.0)"),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        expression.fold_constants().unwrap();
+        let string = expression.to_string();
+        assert_eq!(string, printed, "{source}");
+
+        let mut reparsed = Expression::<Std>::new(string);
+        reparsed.parse().unwrap();
+        reparsed.fold_constants().unwrap();
+        assert_eq!(reparsed.to_string(), printed, "{source}");
+    }
+
+    #[cfg(feature = "decimal")]
+    for (source, printed) in [
+        ("1e1+x", "10.0 + x"),
+        ("2.5*4-x", "10.0 - x"),
+        ("0-1e1", "(0 - 10.0)"),
+        ("0.0*(0-1)", "0.0"),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.set_decimal_literals(true);
+        expression.parse().unwrap();
+        expression.fold_constants().unwrap();
+        let string = expression.to_string();
+        assert_eq!(string, printed, "{source}");
+
+        let mut reparsed = Expression::<Std>::new(string);
+        reparsed.set_decimal_literals(true);
+        reparsed.parse().unwrap();
+        reparsed.fold_constants().unwrap();
+        assert_eq!(reparsed.to_string(), printed, "{source}");
+    }
+
+    let mut expression = Expression::<Std>::new(String::from("tau*2+round(e)"));
+    expression.parse().unwrap();
+    assert_eq!(
+        expression.to_string(),
+        "6.283185307179586 * 2 + std::round(2.718281828459045)"
+    );
+}
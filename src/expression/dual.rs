@@ -0,0 +1,267 @@
+use std::{
+    any::Any,
+    collections::{hash_map::Entry, HashMap},
+};
+
+use smallvec::SmallVec;
+
+use super::Expression;
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::Value,
+};
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+/// Value of an expression together with its derivatives with respect to selected variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dual {
+    pub value: Value,
+    /// Derivative with respect to each selected variable, shaped like `value`
+    pub gradient: Vec<Value>,
+}
+
+/// Derivatives of a value with respect to the selected variables.
+#[derive(Debug, Clone)]
+enum Tangent {
+    /// Independent of all selected variables
+    Zero,
+    Scalar(Vec<f64>),
+    List(Vec<Tangent>),
+}
+impl Tangent {
+    /// Derivative of `value` with respect to the variable at `variable`.
+    fn to_value(&self, value: &Value, variable: usize) -> Value {
+        match (self, value) {
+            (Self::Scalar(gradient), _) => Value::Float(gradient[variable]),
+            (Self::List(items), Value::List(values)) => Value::List(
+                items
+                    .iter()
+                    .zip(values)
+                    .map(|(item, value)| item.to_value(value, variable))
+                    .collect(),
+            ),
+            (_, Value::List(values)) => Value::List(
+                values
+                    .iter()
+                    .map(|value| Self::Zero.to_value(value, variable))
+                    .collect(),
+            ),
+            _ => Value::Float(0.0),
+        }
+    }
+    fn scalar(&self, variables: usize) -> Result<Option<&[f64]>, Error> {
+        match self {
+            Self::Zero => Ok(None),
+            Self::Scalar(gradient) => Ok(Some(&gradient[..variables])),
+            Self::List(_) => Err(Error::InvalidType),
+        }
+    }
+}
+/// Linear combination of the scalar tangents in `terms`, each weighted by its factor.
+fn combine(variables: usize, terms: &[(f64, Option<&[f64]>)]) -> Tangent {
+    if terms.iter().all(|(_, tangent)| tangent.is_none()) {
+        return Tangent::Zero;
+    }
+    let mut gradient = vec![0.0; variables];
+    for (factor, tangent) in terms {
+        if let Some(tangent) = tangent {
+            for (sum, derivative) in gradient.iter_mut().zip(*tangent) {
+                *sum += factor * derivative;
+            }
+        }
+    }
+    Tangent::Scalar(gradient)
+}
+type Partials<T> = HashMap<(ElementIndex, usize), Expression<T>>;
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Evaluates the expression together with its derivatives with respect to `variables`,
+    /// in a single pass using dual numbers and the derivatives declared by the library for its functions.
+    pub fn eval_dual_with_context(
+        &self,
+        context: &mut T::Context,
+        variables: &[&str],
+    ) -> Result<Dual, Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let (value, tangent) =
+            self.eval_dual_recursive(root, context, variables, &mut HashMap::new())?;
+        let gradient = (0..variables.len())
+            .map(|variable| tangent.to_value(&value, variable))
+            .collect();
+        Ok(Dual { value, gradient })
+    }
+    fn eval_dual_recursive(
+        &self,
+        index: ElementIndex,
+        context: &mut T::Context,
+        variables: &[&str],
+        partials: &mut Partials<T>,
+    ) -> Result<(Value, Tangent), Error> {
+        let count = variables.len();
+        Ok(match &self.storage.elements[index] {
+            Node::Literal(value) => (value.clone(), Tangent::Zero),
+            Node::Variable(variable) => {
+                let value = self.storage.variables[*variable].clone();
+                let name = self.storage.variables.name(*variable);
+                match variables.iter().position(|selected| *selected == name) {
+                    Some(_) if matches!(value, Value::List(_)) => return Err(Error::InvalidType),
+                    Some(position) => {
+                        let mut gradient = vec![0.0; count];
+                        gradient[position] = 1.0;
+                        (value, Tangent::Scalar(gradient))
+                    }
+                    None => (value, Tangent::Zero),
+                }
+            }
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                let (u, du) = self.eval_dual_recursive(*lhs, context, variables, partials)?;
+                let (v, dv) = self.eval_dual_recursive(*rhs, context, variables, partials)?;
+                let value = operator.eval(u.clone(), v.clone(), self.arithmetic)?;
+                let (u, v): (f64, f64) = (u.try_into()?, v.try_into()?);
+                let (du, dv) = (du.scalar(count)?, dv.scalar(count)?);
+                let terms = match operator {
+                    Operator::Add => [(1.0, du), (1.0, dv)],
+                    Operator::Sub => [(1.0, du), (-1.0, dv)],
+                    Operator::Mul => [(v, du), (u, dv)],
+                    Operator::Div => [(1.0 / v, du), (-u / (v * v), dv)],
+                    Operator::Pow if dv.is_none() => [(v * u.powf(v - 1.0), du), (0.0, None)],
+                    Operator::Pow => [(v * u.powf(v - 1.0), du), (u.powf(v) * u.ln(), dv)],
+                    Operator::Rem => [(1.0, du), (-(u / v).trunc(), dv)],
+                };
+                (value, combine(count, &terms))
+            }
+            Node::Function(Function { function, args }) => {
+                let signature = function.signature();
+                let mut values = SmallVec::<[Value; T::MAX_ARGS]>::new();
+                let mut tangents = SmallVec::<[Tangent; T::MAX_ARGS]>::new();
+                for (position, arg) in args.iter().enumerate() {
+                    let (value, tangent) =
+                        self.eval_dual_recursive(*arg, context, variables, partials)?;
+                    let expected = signature.param(position).ok_or(Error::InvalidArg)?;
+                    if !self.coercion.accepts(expected, value.value_type()) {
+                        return Err(Error::InvalidType);
+                    }
+                    values.push(value);
+                    tangents.push(tangent);
+                }
+                let value = function.call(context, &values)?;
+                let params = function.info().params;
+                let mut terms = Vec::new();
+                for (param, tangent) in tangents.iter().enumerate() {
+                    let Some(tangent) = tangent
+                        .scalar(count)
+                        .map_err(|_| Error::NotDifferentiable(function.info().qualified_name()))?
+                    else {
+                        continue;
+                    };
+                    let partial = match partials.entry((index, param)) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(Expression::partial(function, param)?),
+                    };
+                    for (name, value) in params.iter().zip(&values) {
+                        // parameters the derivative doesn't use aren't variables of it
+                        partial.set_variable(name, value.clone()).ok();
+                    }
+                    let factor: f64 = partial.eval_with_context(context)?.try_into()?;
+                    terms.push((factor, Some(tangent)));
+                }
+                (value, combine(count, &terms))
+            }
+            Node::List(List { items }) => {
+                let mut values = Vec::with_capacity(items.len());
+                let mut tangents = Vec::with_capacity(items.len());
+                for item in items {
+                    let (value, tangent) =
+                        self.eval_dual_recursive(*item, context, variables, partials)?;
+                    values.push(value);
+                    tangents.push(tangent);
+                }
+                let tangent = if tangents
+                    .iter()
+                    .all(|tangent| matches!(tangent, Tangent::Zero))
+                {
+                    Tangent::Zero
+                } else {
+                    Tangent::List(tangents)
+                };
+                (Value::List(values), tangent)
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                let (list, tangent) =
+                    self.eval_dual_recursive(*list, context, variables, partials)?;
+                let (index, _) = self.eval_dual_recursive(*index, context, variables, partials)?;
+                let list: Vec<Value> = list.try_into()?;
+                let index: i64 = index.try_into()?;
+                let index = usize::try_from(index)
+                    .ok()
+                    .filter(|index| *index < list.len())
+                    .ok_or(Error::InvalidIndex)?;
+                let tangent = match tangent {
+                    Tangent::List(mut items) => items.swap_remove(index),
+                    _ => Tangent::Zero,
+                };
+                (list.into_iter().nth(index).unwrap(), tangent)
+            }
+        })
+    }
+}
+impl<T> Expression<T>
+where
+    T: Library<T, Context = dyn Any>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Evaluates the expression of a library which doesn't use a context, together with its derivatives.
+    pub fn eval_dual(&self, variables: &[&str]) -> Result<Dual, Error> {
+        self.eval_dual_with_context(&mut (), variables)
+    }
+}
+#[test]
+fn eval_dual() {
+    let mut expression = Expression::<Std>::new(String::from("x^2*y + sin(x) + [x, 3*y][1]"));
+    expression.parse().unwrap();
+    expression.set_variable("x", 2.0).unwrap();
+    expression.set_variable("y", 3).unwrap();
+    let dual = expression.eval_dual(&["x", "y", "z"]).unwrap();
+    assert_eq!(dual.value, Value::Float(21.0 + 2.0f64.sin()));
+    assert_eq!(
+        dual.gradient,
+        [
+            Value::Float(12.0 + 2.0f64.cos()),
+            Value::Float(7.0),
+            Value::Float(0.0)
+        ]
+    );
+
+    let mut expression = Expression::<Std>::new(String::from("[x^3, x]"));
+    expression.parse().unwrap();
+    expression.set_variable("x", 2).unwrap();
+    let dual = expression.eval_dual(&["x"]).unwrap();
+    assert_eq!(
+        dual.gradient,
+        [Value::List(vec![Value::Float(12.0), Value::Float(1.0)])]
+    );
+
+    // the remainder truncates towards zero, so u % v = u - trunc(u / v) * v
+    let mut expression = Expression::<Std>::new(String::from("u%v"));
+    expression.parse().unwrap();
+    expression.set_variable("u", -7).unwrap();
+    expression.set_variable("v", 2).unwrap();
+    let dual = expression.eval_dual(&["u", "v"]).unwrap();
+    assert_eq!(dual.value, Value::Int(-1));
+    assert_eq!(dual.gradient, [Value::Float(1.0), Value::Float(3.0)]);
+
+    let mut expression = Expression::<Std>::new(String::from("print(x)"));
+    expression.parse().unwrap();
+    assert!(matches!(
+        expression.eval_dual(&["x"]),
+        Err(Error::NotDifferentiable(name)) if name == "std::print"
+    ));
+}
@@ -1,12 +1,19 @@
-use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::ops::{Add, Sub};
 
 use smallvec::SmallVec;
 
 use crate::{
-    expression::expression_storage::variables::VariableIndex, library::Library, value::Value,
+    error::Error,
+    expression::expression_storage::variables::VariableIndex,
+    library::Library,
+    value::{Arithmetic, Value},
 };
 
+#[cfg(feature = "decimal")]
+use crate::value::Decimal;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(transparent)]
 pub struct ElementIndex(pub(crate) usize);
 impl ElementIndex {
@@ -29,6 +36,11 @@ impl Sub for ElementIndex {
     }
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
 pub(crate) enum Node<T>
 where
     T: Library<T>,
@@ -38,14 +50,28 @@ where
     Literal(Value),
     Variable(VariableIndex),
     Function(Function<T>),
+    List(List),
+    Subscript(Subscript),
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Instruction {
     pub operator: Operator,
     pub lhs: ElementIndex,
     pub rhs: ElementIndex,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct List {
+    pub items: Vec<ElementIndex>,
+}
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Subscript {
+    pub list: ElementIndex,
+    pub index: ElementIndex,
+}
+#[derive(Debug, Clone)]
 pub(crate) struct Function<T>
 where
     T: Library<T>,
@@ -54,6 +80,44 @@ where
     pub function: T,
     pub args: SmallVec<[ElementIndex; T::MAX_ARGS]>,
 }
+/// Functions are serialized by their qualified name and resolved with [`Library::from_qualified_name`] on load.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Function<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut function = serializer.serialize_struct("Function", 2)?;
+        function.serialize_field("function", &self.function.info().qualified_name())?;
+        function.serialize_field("args", self.args.as_slice())?;
+        function.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Function<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Function")]
+        struct Qualified {
+            function: String,
+            args: Vec<ElementIndex>,
+        }
+        let Qualified { function, args } = Qualified::deserialize(deserializer)?;
+        let function = T::from_qualified_name(&function)
+            .map_err(|_| serde::de::Error::custom(format_args!("unknown function `{function}`")))?;
+        Ok(Self {
+            function,
+            args: args.into_iter().collect(),
+        })
+    }
+}
 
 macro_rules! impl_from_node {
     ($item:ty, $variant:ident) => {
@@ -116,21 +180,28 @@ where
     [(); T::MAX_ARGS]:,
 {
     impl_node_as! {as_instruction, Instruction, Instruction}
-    impl_node_as! {as_variable, VariableIndex, Variable}
-    impl_node_as! {as_value, Value, Literal}
-    impl_node_as! {as_function, Function<T>, Function}
-
     impl_node_as! {as_mut_instruction, Instruction, Instruction, mut}
-    impl_node_as! {as_mut_variable, VariableIndex, Variable, mut}
-    impl_node_as! {as_mut_value, Value, Literal, mut}
-    impl_node_as! {as_mut_function, Function<T>, Function, mut}
+
+    /// Indices of the elements this node takes as operands, which may be replaced.
+    pub(crate) fn operands_mut(&mut self) -> Vec<&mut ElementIndex> {
+        match self {
+            Self::Instruction(Instruction { lhs, rhs, .. }) => vec![lhs, rhs],
+            Self::Function(Function { args, .. }) => args.iter_mut().collect(),
+            Self::List(List { items }) => items.iter_mut().collect(),
+            Self::Subscript(Subscript { list, index }) => vec![list, index],
+            Self::Literal(_) | Self::Variable(_) => Vec::new(),
+        }
+    }
 }
 impl_node_convert! {Instruction, Instruction}
 impl_node_convert! {VariableIndex, Variable}
 impl_node_convert! {Value, Literal}
 impl_node_convert! {Function<T>, Function}
+impl_node_convert! {List, List}
+impl_node_convert! {Subscript, Subscript}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Operator {
     Add,
     Sub,
@@ -138,18 +209,6 @@ pub(crate) enum Operator {
     Div,
     Rem,
     Pow,
-
-    Not,
-    Or,
-    Xor,
-    And,
-
-    Eq,
-    NEq,
-    GEq,
-    Gt,
-    LEq,
-    Lt,
 }
 
 impl Operator {
@@ -162,38 +221,198 @@ impl Operator {
             Self::Div => 21,
             Self::Rem => 22,
             Self::Pow => 22,
-
-            Self::Not => 1,
-            Self::Eq => 2,
-            Self::And => 3,
-            Self::Or => 4,
-            Self::Xor => 5,
-            Self::NEq => 7,
-            Self::Gt => 8,
-            Self::GEq => 9,
-            Self::Lt => 10,
-            Self::LEq => 11,
         }
     }
-    pub(crate) fn eval(&self, lhs: Value, rhs: Value) -> Value {
-        match (lhs, rhs) {
-            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(self.eval_generic(lhs, rhs)),
-            (Value::Int(lhs), Value::Int(rhs)) => Value::Int(self.eval_generic(lhs, rhs)),
-            _ => unreachable!(),
+    pub(crate) fn eval(
+        &self,
+        lhs: Value,
+        rhs: Value,
+        arithmetic: Arithmetic,
+    ) -> Result<Value, Error> {
+        Ok(match (lhs, rhs) {
+            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(self.eval_float(lhs, rhs)),
+            (Value::Int(lhs), Value::Int(rhs)) => Value::Int(self.eval_int(lhs, rhs, arithmetic)?),
+            (Value::Int(lhs), Value::Float(rhs)) => Value::Float(self.eval_float(lhs as f64, rhs)),
+            (Value::Float(lhs), Value::Int(rhs)) => Value::Float(self.eval_float(lhs, rhs as f64)),
+            #[cfg(feature = "decimal")]
+            (
+                lhs @ (Value::Int(_) | Value::Decimal(_)),
+                rhs @ (Value::Int(_) | Value::Decimal(_)),
+            ) => Value::Decimal(self.eval_decimal(lhs.try_into()?, rhs.try_into()?)?),
+            #[cfg(feature = "decimal")]
+            (lhs @ Value::Decimal(_), Value::Float(rhs)) => {
+                Value::Float(self.eval_float(lhs.try_into()?, rhs))
+            }
+            #[cfg(feature = "decimal")]
+            (Value::Float(lhs), rhs @ Value::Decimal(_)) => {
+                Value::Float(self.eval_float(lhs, rhs.try_into()?))
+            }
+            _ => return Err(Error::InvalidType),
+        })
+    }
+    fn eval_int(self, lhs: i64, rhs: i64, arithmetic: Arithmetic) -> Result<i64, Error> {
+        if matches!(self, Self::Div | Self::Rem) && rhs == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        if matches!(self, Self::Pow) && rhs < 0 {
+            return Err(Error::NegativeExponent);
+        }
+        // exponents beyond `u32` only fit for the bases -1, 0 and 1
+        let exponent = u32::try_from(rhs).ok();
+        match arithmetic {
+            Arithmetic::Checked => match self {
+                Self::Add => lhs.checked_add(rhs),
+                Self::Sub => lhs.checked_sub(rhs),
+                Self::Mul => lhs.checked_mul(rhs),
+                Self::Div => lhs.checked_div(rhs),
+                Self::Rem => lhs.checked_rem(rhs),
+                Self::Pow => exponent
+                    .map_or_else(|| unit_pow(lhs, rhs), |exponent| lhs.checked_pow(exponent)),
+            }
+            .ok_or(Error::Overflow),
+            Arithmetic::Wrapping => Ok(match self {
+                Self::Add => lhs.wrapping_add(rhs),
+                Self::Sub => lhs.wrapping_sub(rhs),
+                Self::Mul => lhs.wrapping_mul(rhs),
+                Self::Div => lhs.wrapping_div(rhs),
+                Self::Rem => lhs.wrapping_rem(rhs),
+                Self::Pow => wrapping_pow(lhs, rhs),
+            }),
+            Arithmetic::Saturating => Ok(match self {
+                Self::Add => lhs.saturating_add(rhs),
+                Self::Sub => lhs.saturating_sub(rhs),
+                Self::Mul => lhs.saturating_mul(rhs),
+                Self::Div => lhs.saturating_div(rhs),
+                // only `i64::MIN % -1` overflows, whose remainder is 0
+                Self::Rem => lhs.wrapping_rem(rhs),
+                Self::Pow => match exponent {
+                    Some(exponent) => lhs.saturating_pow(exponent),
+                    None => unit_pow(lhs, rhs).unwrap_or(if lhs < 0 && rhs % 2 == 1 {
+                        i64::MIN
+                    } else {
+                        i64::MAX
+                    }),
+                },
+            }),
+        }
+    }
+    /// Decimal arithmetic is always checked, as it has no natural bounds to wrap or saturate at.
+    #[cfg(feature = "decimal")]
+    fn eval_decimal(self, lhs: Decimal, rhs: Decimal) -> Result<Decimal, Error> {
+        use rust_decimal::MathematicalOps;
+
+        if matches!(self, Self::Div | Self::Rem) && rhs.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        // a fractional power of a negative base isn't real, for which floats give NaN
+        if matches!(self, Self::Pow) && lhs < Decimal::ZERO && !rhs.fract().is_zero() {
+            return Err(Error::InvalidType);
         }
+        match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Sub => lhs.checked_sub(rhs),
+            Self::Mul => lhs.checked_mul(rhs),
+            Self::Div => lhs.checked_div(rhs),
+            Self::Rem => lhs.checked_rem(rhs),
+            Self::Pow => match i64::try_from(rhs) {
+                Ok(exponent) if rhs.fract().is_zero() => lhs.checked_powi(exponent),
+                _ => lhs.checked_powd(rhs),
+            },
+        }
+        .ok_or(Error::Overflow)
     }
-    fn eval_generic<T>(self, lhs: T, rhs: T) -> T
-    where
-        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Rem<Output = T>,
-    {
+    fn eval_float(self, lhs: f64, rhs: f64) -> f64 {
         match self {
             Self::Add => lhs + rhs,
             Self::Sub => lhs - rhs,
             Self::Mul => lhs * rhs,
             Self::Div => lhs / rhs,
             Self::Rem => lhs % rhs,
-            Self::Pow => todo!(),
-            _ => todo!(),
+            Self::Pow => lhs.powf(rhs),
+        }
+    }
+}
+/// `lhs ^ rhs` for the bases whose powers never overflow.
+fn unit_pow(lhs: i64, rhs: i64) -> Option<i64> {
+    match lhs {
+        -1 if rhs % 2 == 1 => Some(-1),
+        -1 | 1 => Some(1),
+        0 => Some(0),
+        _ => None,
+    }
+}
+/// `lhs ^ rhs` wrapped at the bounds of `i64`, by repeated squaring for any non-negative exponent.
+fn wrapping_pow(mut lhs: i64, mut rhs: i64) -> i64 {
+    let mut power = 1i64;
+    while rhs > 0 {
+        if rhs & 1 == 1 {
+            power = power.wrapping_mul(lhs);
+        }
+        lhs = lhs.wrapping_mul(lhs);
+        rhs >>= 1;
+    }
+    power
+}
+#[test]
+fn eval_int_pow() {
+    let huge = 1 << 40;
+    let pow =
+        |lhs: i64, rhs: i64, arithmetic| Operator::Pow.eval(lhs.into(), rhs.into(), arithmetic);
+    for arithmetic in [
+        Arithmetic::Checked,
+        Arithmetic::Wrapping,
+        Arithmetic::Saturating,
+    ] {
+        assert!(matches!(
+            pow(2, -1, arithmetic),
+            Err(Error::NegativeExponent)
+        ));
+        assert_eq!(pow(3, 4, arithmetic).unwrap(), Value::Int(81));
+        for (base, power) in [(0, 0), (1, 1), (-1, 1)] {
+            assert_eq!(pow(base, huge, arithmetic).unwrap(), Value::Int(power));
         }
+        assert_eq!(pow(-1, huge + 1, arithmetic).unwrap(), Value::Int(-1));
+    }
+    assert!(matches!(
+        pow(2, huge, Arithmetic::Checked),
+        Err(Error::Overflow)
+    ));
+    assert_eq!(
+        pow(2, huge, Arithmetic::Saturating).unwrap(),
+        Value::Int(i64::MAX)
+    );
+    assert_eq!(
+        pow(-2, huge + 1, Arithmetic::Saturating).unwrap(),
+        Value::Int(i64::MIN)
+    );
+    assert_eq!(pow(2, huge, Arithmetic::Wrapping).unwrap(), Value::Int(0));
+    let half = 3i64.wrapping_pow(1 << 31);
+    assert_eq!(
+        pow(3, 1 << 32, Arithmetic::Wrapping).unwrap(),
+        Value::Int(half.wrapping_mul(half))
+    );
+    assert_eq!(
+        pow(3, 63, Arithmetic::Wrapping).unwrap(),
+        Value::Int(3i64.wrapping_pow(63))
+    );
+}
+#[cfg(feature = "decimal")]
+#[test]
+fn eval_decimal() {
+    let decimal = |string: &str| Value::Decimal(string.parse().unwrap());
+    for (operator, result) in [
+        (Operator::Add, "3.5"),
+        (Operator::Sub, "-0.5"),
+        (Operator::Mul, "3"),
+        (Operator::Div, "0.75"),
+        (Operator::Rem, "1.5"),
+        (Operator::Pow, "2.25"),
+    ] {
+        let value = operator.eval(decimal("1.5"), decimal("2"), Arithmetic::Checked);
+        assert_eq!(value.unwrap(), decimal(result), "{operator:?}");
     }
+    let root = Operator::Pow.eval(decimal("-2.5"), decimal("0.5"), Arithmetic::Checked);
+    assert!(matches!(root, Err(Error::InvalidType)));
+    let square = Operator::Pow.eval(decimal("-2.5"), decimal("2"), Arithmetic::Checked);
+    assert_eq!(square.unwrap(), decimal("6.25"));
 }
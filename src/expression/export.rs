@@ -0,0 +1,212 @@
+use std::fmt::Write;
+
+use super::{Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Subscript},
+    library::Library,
+    value::Value,
+};
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Graphviz graph of the parsed expression, with one node per element and an edge to each operand or argument.
+    pub fn to_dot(&self) -> Result<String, Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let mut dot = String::from("digraph expression {\n");
+        let mut visited = vec![false; self.storage.elements.len()];
+        self.storage.write_dot(&mut dot, root, &mut visited);
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+    /// Tree of the parsed expression as JSON, with an object per element tagged by its `type`.
+    pub fn to_json_ast(&self) -> Result<String, Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let mut json = String::new();
+        self.storage.write_json(&mut json, root);
+        Ok(json)
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Label of the element at `index`, without its operands.
+    fn label(&self, index: ElementIndex) -> String {
+        match &self.elements[index] {
+            Node::Instruction(Instruction { operator, .. }) => operator.to_string(),
+            Node::Literal(value) => value.to_string(),
+            Node::Variable(variable) => self.variables.name(*variable).to_owned(),
+            Node::Function(Function { function, .. }) => function.info().qualified_name(),
+            Node::List(_) => String::from("[]"),
+            Node::Subscript(_) => String::from("[index]"),
+        }
+    }
+    /// Operands of the element at `index` with the label of their edge.
+    fn operands(&self, index: ElementIndex) -> Vec<(String, ElementIndex)> {
+        match &self.elements[index] {
+            Node::Instruction(Instruction { lhs, rhs, .. }) => {
+                vec![(String::from("lhs"), *lhs), (String::from("rhs"), *rhs)]
+            }
+            Node::Function(Function { args, .. }) => numbered(args),
+            Node::List(List { items }) => numbered(items),
+            Node::Subscript(Subscript { list, index }) => {
+                vec![
+                    (String::from("list"), *list),
+                    (String::from("index"), *index),
+                ]
+            }
+            Node::Literal(_) | Node::Variable(_) => Vec::new(),
+        }
+    }
+    fn write_dot(&self, dot: &mut String, index: ElementIndex, visited: &mut [bool]) {
+        if std::mem::replace(&mut visited[index.0], true) {
+            return;
+        }
+        let label = escape(&self.label(index));
+        writeln!(dot, "    n{} [label=\"{label}\"];", index.0).unwrap();
+        for (edge, operand) in self.operands(index) {
+            writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{edge}\"];",
+                index.0, operand.0
+            )
+            .unwrap();
+            self.write_dot(dot, operand, visited);
+        }
+    }
+    fn write_json(&self, json: &mut String, index: ElementIndex) {
+        match &self.elements[index] {
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                write!(
+                    json,
+                    r#"{{"type":"instruction","operator":"{operator}","lhs":"#
+                )
+                .unwrap();
+                self.write_json(json, *lhs);
+                json.push_str(r#","rhs":"#);
+                self.write_json(json, *rhs);
+                json.push('}');
+            }
+            Node::Literal(value) => {
+                json.push_str(r#"{"type":"literal","value":"#);
+                write_json_value(json, value);
+                json.push('}');
+            }
+            Node::Variable(variable) => {
+                let name = escape(self.variables.name(*variable));
+                write!(json, r#"{{"type":"variable","name":"{name}"}}"#).unwrap();
+            }
+            Node::Function(Function { function, args }) => {
+                let name = escape(&function.info().qualified_name());
+                write!(json, r#"{{"type":"function","name":"{name}","args":"#).unwrap();
+                self.write_json_items(json, args);
+                json.push('}');
+            }
+            Node::List(List { items }) => {
+                json.push_str(r#"{"type":"list","items":"#);
+                self.write_json_items(json, items);
+                json.push('}');
+            }
+            Node::Subscript(Subscript { list, index }) => {
+                json.push_str(r#"{"type":"subscript","list":"#);
+                self.write_json(json, *list);
+                json.push_str(r#","index":"#);
+                self.write_json(json, *index);
+                json.push('}');
+            }
+        }
+    }
+    fn write_json_items(&self, json: &mut String, items: &[ElementIndex]) {
+        json.push('[');
+        for (count, item) in items.iter().enumerate() {
+            if count > 0 {
+                json.push(',');
+            }
+            self.write_json(json, *item);
+        }
+        json.push(']');
+    }
+}
+fn numbered(items: &[ElementIndex]) -> Vec<(String, ElementIndex)> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(count, item)| (count.to_string(), *item))
+        .collect()
+}
+/// Writes `value` as a JSON number or array, non-finite floats and decimals are written as strings.
+fn write_json_value(json: &mut String, value: &Value) {
+    match value {
+        Value::Int(int) => write!(json, "{int}").unwrap(),
+        Value::Float(float) if float.is_finite() => write!(json, "{float:?}").unwrap(),
+        Value::Float(float) => write!(json, "\"{float}\"").unwrap(),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(decimal) => write!(json, "\"{decimal}\"").unwrap(),
+        Value::List(list) => {
+            json.push('[');
+            for (count, item) in list.iter().enumerate() {
+                if count > 0 {
+                    json.push(',');
+                }
+                write_json_value(json, item);
+            }
+            json.push(']');
+        }
+    }
+}
+/// Escapes quotes and backslashes, which is sufficient for both DOT and JSON strings of names.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for chr in string.chars() {
+        if matches!(chr, '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(chr);
+    }
+    escaped
+}
+#[test]
+fn export() {
+    let mut expression = Expression::<Std>::new(String::from("sqrt(x)*[1,inf][0]"));
+    assert!(matches!(expression.to_dot(), Err(Error::NotCompiled)));
+    expression.parse().unwrap();
+    assert_eq!(
+        expression.to_dot().unwrap(),
+        r#"digraph expression {
+    n2 [label="*"];
+    n2 -> n1 [label="lhs"];
+    n1 [label="std::sqrt"];
+    n1 -> n0 [label="0"];
+    n0 [label="x"];
+    n2 -> n7 [label="rhs"];
+    n7 [label="[index]"];
+    n7 -> n5 [label="list"];
+    n5 [label="[]"];
+    n5 -> n3 [label="0"];
+    n3 [label="1"];
+    n5 -> n4 [label="1"];
+    n4 [label="inf"];
+    n7 -> n6 [label="index"];
+    n6 [label="0"];
+}
+"#
+    );
+    assert_eq!(
+        expression.to_json_ast().unwrap(),
+        concat!(
+            r#"{"type":"instruction","operator":"*","#,
+            r#""lhs":{"type":"function","name":"std::sqrt","args":[{"type":"variable","name":"x"}]},"#,
+            r#""rhs":{"type":"subscript","#,
+            r#""list":{"type":"list","items":[{"type":"literal","value":1},{"type":"literal","value":"inf"}]},"#,
+            r#""index":{"type":"literal","value":0}}}"#
+        )
+    );
+}
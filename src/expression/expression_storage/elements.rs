@@ -4,13 +4,23 @@ use crate::{
     expression::element::{ElementIndex, Node},
     library::Library,
 };
+
+#[cfg(feature = "serde")]
+use crate::{error::Error, expression::expression_storage::variables::Variables};
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
 pub(crate) struct Elements<T>
 where
     T: Library<T>,
     [(); T::MAX_ARGS]:,
 {
     elements: Vec<Node<T>>,
+    /// Position in the expression string each element was parsed from
+    positions: Vec<usize>,
 }
 
 impl<T> Elements<T>
@@ -18,15 +28,81 @@ where
     T: Library<T>,
     [(); T::MAX_ARGS]:,
 {
-    pub(crate) fn push_node(&mut self, node: impl Into<Node<T>>) -> ElementIndex {
+    pub(crate) fn push_node(&mut self, node: impl Into<Node<T>>, position: usize) -> ElementIndex {
         let index = self.elements.len();
         self.elements.push(node.into());
-        ElementIndex(index)
+        self.positions.push(position);
+        ElementIndex::new(index)
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.elements.len()
+    }
+    pub(crate) fn position(&self, index: ElementIndex) -> usize {
+        self.positions[index.0]
     }
 
     pub fn clear(&mut self) {
-        self.elements.clear()
+        self.elements.clear();
+        self.positions.clear();
+    }
+    /// Checks that every element has a position, that its operands and variables exist,
+    /// and that no element is an operand of itself, directly or through other elements.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&mut self, variables: &Variables) -> Result<(), Error> {
+        if self.positions.len() != self.elements.len() {
+            return Err(Error::InvalidIndex);
+        }
+        let mut operands = Vec::with_capacity(self.elements.len());
+        for node in self.elements.iter_mut() {
+            if let Node::Variable(variable) = node {
+                if !variables.contains(*variable) {
+                    return Err(Error::InvalidVariable);
+                }
+            }
+            let node_operands = node
+                .operands_mut()
+                .into_iter()
+                .map(|operand| *operand)
+                .collect::<Vec<_>>();
+            if node_operands
+                .iter()
+                .any(|operand| operand.0 >= self.positions.len())
+            {
+                return Err(Error::InvalidIndex);
+            }
+            operands.push(node_operands);
+        }
+        let mut visits = vec![Visit::Pending; operands.len()];
+        for index in 0..operands.len() {
+            if !acyclic(&operands, index, &mut visits) {
+                return Err(Error::InvalidIndex);
+            }
+        }
+        Ok(())
+    }
+}
+/// State of an element during the search for cycles
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Pending,
+    /// Element whose operands are being visited, which is an operand of itself if it is reached again
+    Active,
+    Done,
+}
+/// Whether the element at `index` doesn't reach itself through the `operands` of the elements.
+#[cfg(feature = "serde")]
+fn acyclic(operands: &[Vec<ElementIndex>], index: usize, visits: &mut [Visit]) -> bool {
+    match visits[index] {
+        Visit::Done => return true,
+        Visit::Active => return false,
+        Visit::Pending => visits[index] = Visit::Active,
     }
+    let acyclic = operands[index]
+        .iter()
+        .all(|operand| acyclic(operands, operand.0, visits));
+    visits[index] = Visit::Done;
+    acyclic
 }
 impl<T> Index<ElementIndex> for Elements<T>
 where
@@ -58,6 +134,7 @@ where
     fn default() -> Self {
         Self {
             elements: Default::default(),
+            positions: Default::default(),
         }
     }
 }
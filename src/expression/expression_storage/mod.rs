@@ -1,4 +1,12 @@
-use crate::library::Library;
+use std::collections::HashSet;
+
+use crate::{
+    expression::{element::ElementIndex, scope::Scope},
+    library::Library,
+};
+
+#[cfg(feature = "serde")]
+use crate::error::Error;
 
 use self::{elements::Elements, variables::Variables};
 
@@ -6,6 +14,11 @@ pub mod elements;
 pub mod variables;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
 pub(crate) struct ExpressionStorage<T>
 where
     T: Library<T>,
@@ -13,6 +26,15 @@ where
 {
     pub elements: Elements<T>,
     pub variables: Variables,
+    /// Aliases and default namespaces used to resolve names
+    pub scope: Scope,
+    /// Length of the last parsed input, used to locate the remaining input
+    pub length: usize,
+    /// Elements which are operands of several others, whose values are remembered during an evaluation
+    pub shared: HashSet<ElementIndex>,
+    /// Whether literals with a fractional part are parsed as exact decimals instead of floats
+    #[cfg(feature = "decimal")]
+    pub decimal_literals: bool,
 }
 impl<T> ExpressionStorage<T>
 where
@@ -22,6 +44,27 @@ where
     pub(crate) fn clear(&mut self) {
         self.elements.clear();
         self.variables.clear();
+        self.shared.clear();
+    }
+    /// Checks that `root` and every index stored in the elements are in bounds
+    /// and that the elements don't form a cycle, as stored expressions may have been edited.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&mut self, root: Option<ElementIndex>) -> Result<(), Error> {
+        self.variables.validate()?;
+        self.elements.validate(&self.variables)?;
+        let len = self.elements.len();
+        if root
+            .into_iter()
+            .chain(self.shared.iter().copied())
+            .any(|index| index.0 >= len)
+        {
+            return Err(Error::InvalidIndex);
+        }
+        Ok(())
+    }
+    /// Position of the remaining `input` in the last parsed input.
+    pub(crate) fn position(&self, input: &[u8]) -> usize {
+        self.length - input.len()
     }
 }
 impl<T> Default for ExpressionStorage<T>
@@ -33,6 +76,11 @@ where
         Self {
             elements: Default::default(),
             variables: Default::default(),
+            scope: Default::default(),
+            length: 0,
+            shared: Default::default(),
+            #[cfg(feature = "decimal")]
+            decimal_literals: false,
         }
     }
 }
@@ -1,12 +1,14 @@
-use std::{collections::HashMap, ops::Index};
+use std::{borrow::Borrow, collections::HashMap, ops::Index};
 
 use smallvec::SmallVec;
 
-use crate::{small_string::SmallString, value::Value};
+use crate::{error::Error, small_string::SmallString, value::Value};
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(transparent)]
 pub struct VariableIndex(pub(crate) usize);
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Variables {
     identifiers: HashMap<SmallString<16>, usize>,
     values: SmallVec<[Value; 4]>,
@@ -14,20 +16,45 @@ pub(crate) struct Variables {
 
 impl Variables {
     pub fn clear(&mut self) {
+        self.identifiers.clear();
         self.values.clear();
-        self.values.clear();
-    }
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            identifiers: HashMap::with_capacity(capacity),
-            values: SmallVec::with_capacity(capacity),
-        }
     }
     pub(crate) fn push(&mut self, identifier: &str) {
         self.identifiers
             .insert(identifier.into(), self.values.len());
         self.values.push(Value::Int(0));
     }
+    pub(crate) fn set(&mut self, identifier: &str, value: Value) -> Result<(), Error> {
+        let index = self
+            .identifiers
+            .get(identifier)
+            .ok_or(Error::InvalidVariable)?;
+        self.values[*index] = value;
+        Ok(())
+    }
+    /// Whether there is a variable at `index`.
+    #[cfg(feature = "serde")]
+    pub(crate) fn contains(&self, index: VariableIndex) -> bool {
+        index.0 < self.values.len()
+    }
+    /// Checks that every identifier refers to a value.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let len = self.values.len();
+        if self.identifiers.values().all(|index| *index < len) {
+            Ok(())
+        } else {
+            Err(Error::InvalidVariable)
+        }
+    }
+    /// Identifier of the variable at `index`.
+    pub(crate) fn name(&self, index: VariableIndex) -> &str {
+        self.identifiers
+            .iter()
+            .find(|(_, value)| **value == index.0)
+            .map(|(identifier, _)| identifier.borrow())
+            .unwrap_or_default()
+    }
     pub(crate) fn find_or_set(&mut self, identifier: &str) -> VariableIndex {
         let index = match self.identifiers.get(identifier) {
             Some(index) => *index,
@@ -0,0 +1,150 @@
+use super::{Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::{Arithmetic, Coercion, Value},
+};
+use std::any::Any;
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Replaces every subexpression which doesn't depend on a variable by its value,
+    /// passing `context` to the functions called.
+    /// Only functions which are [`Library::is_const`] are called, and subexpressions
+    /// which fail to evaluate are kept, so the error is reported by [`Expression::eval`].
+    pub fn fold_constants_with_context(&mut self, context: &mut T::Context) -> Result<(), Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        self.storage
+            .fold(root, self.coercion, self.arithmetic, Some(context));
+        Ok(())
+    }
+}
+impl<T> Expression<T>
+where
+    T: Library<T, Context = dyn Any>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Folds the constants of an expression whose library doesn't use a context.
+    pub fn fold_constants(&mut self) -> Result<(), Error> {
+        self.fold_constants_with_context(&mut ())
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Folds the subtree at `index` and returns its value if it is constant.
+    /// Function calls are only folded if a `context` is given.
+    pub(crate) fn fold(
+        &mut self,
+        index: ElementIndex,
+        coercion: Coercion,
+        arithmetic: Arithmetic,
+        mut context: Option<&mut T::Context>,
+    ) -> Option<Value> {
+        let value = match self.elements[index].clone_shallow() {
+            Shallow::Literal(value) => return Some(value),
+            Shallow::Variable => return None,
+            Shallow::Instruction(operator, lhs, rhs) => {
+                let lhs = self.fold(lhs, coercion, arithmetic, context.as_deref_mut());
+                let rhs = self.fold(rhs, coercion, arithmetic, context);
+                operator.eval(lhs?, rhs?, arithmetic).ok()?
+            }
+            Shallow::Items(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                let mut constant = true;
+                for item in items {
+                    let value = self.fold(item, coercion, arithmetic, context.as_deref_mut());
+                    constant &= value.is_some();
+                    values.extend(value);
+                }
+                constant.then_some(Value::List(values))?
+            }
+            Shallow::Subscript(list, index) => {
+                let list = self.fold(list, coercion, arithmetic, context.as_deref_mut());
+                let index = self.fold(index, coercion, arithmetic, context);
+                let list: Vec<Value> = list?.try_into().ok()?;
+                let index: i64 = index?.try_into().ok()?;
+                list.into_iter().nth(usize::try_from(index).ok()?)?
+            }
+            Shallow::Function(args) => {
+                let mut values = Vec::with_capacity(args.len());
+                let mut constant = true;
+                for arg in args {
+                    let value = self.fold(arg, coercion, arithmetic, context.as_deref_mut());
+                    constant &= value.is_some();
+                    values.extend(value);
+                }
+                let Node::Function(Function { function, .. }) = &self.elements[index] else {
+                    unreachable!()
+                };
+                if !constant || !function.is_const() {
+                    return None;
+                }
+                let signature = function.signature();
+                let accepted = values.iter().enumerate().all(|(count, value)| {
+                    signature
+                        .param(count)
+                        .is_some_and(|expected| coercion.accepts(expected, value.value_type()))
+                });
+                if !accepted {
+                    return None;
+                }
+                function.call(context?, &values).ok()?
+            }
+        };
+        self.elements[index] = Node::Literal(value.clone());
+        Some(value)
+    }
+}
+/// Kind and operands of a node, detached from the storage so it can be modified while folding.
+enum Shallow {
+    Literal(Value),
+    Variable,
+    Instruction(Operator, ElementIndex, ElementIndex),
+    Items(Vec<ElementIndex>),
+    Subscript(ElementIndex, ElementIndex),
+    Function(Vec<ElementIndex>),
+}
+impl<T> Node<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn clone_shallow(&self) -> Shallow {
+        match self {
+            Node::Literal(value) => Shallow::Literal(value.clone()),
+            Node::Variable(_) => Shallow::Variable,
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                Shallow::Instruction(*operator, *lhs, *rhs)
+            }
+            Node::List(List { items }) => Shallow::Items(items.clone()),
+            Node::Subscript(Subscript { list, index }) => Shallow::Subscript(*list, *index),
+            Node::Function(Function { args, .. }) => Shallow::Function(args.to_vec()),
+        }
+    }
+}
+#[test]
+fn fold() {
+    for (source, folded) in [
+        ("1+2*3", "7"),
+        ("x*(2^3-1)", "x * 7"),
+        ("sqrt(4)+[1,x][0]", "2.0 + [1, x][0]"),
+        ("print(1)+max(1,2)", "std::print(1) + 2"),
+        ("[1,2][5]+x", "[1, 2][5] + x"),
+        ("len(2)", "std::len(2)"),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        expression.fold_constants().unwrap();
+        assert_eq!(expression.to_string(), folded, "{source}");
+    }
+}
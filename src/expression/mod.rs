@@ -1,18 +1,37 @@
-use std::ops::Index;
+use std::{any::Any, collections::HashMap};
 
 use smallvec::SmallVec;
 
-use crate::{error::Error, expression::element::ElementIndex, library::Library, value::Value};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Subscript},
+    library::Library,
+    value::{Arithmetic, Coercion, Value},
+};
 
-use self::expression_storage::{variables::Variables, ExpressionStorage};
+use self::{expression_storage::ExpressionStorage, scope::Scope};
 
-mod element;
+#[cfg(test)]
+use crate::library::{std::Std, Arity};
+
+mod check;
+mod derivative;
+mod display;
+pub mod dual;
+pub(crate) mod element;
+mod export;
 pub(crate) mod expression_storage;
+mod fold;
 mod parse;
+pub mod scope;
+pub mod simplify;
+mod subexpression;
 /// An `Expression` which stores the original expression string and the compiled version of that string.
 /// This allows the expression to be evaluated multiple times without the overhead of being parsed again
 ///
+/// With the `serde` feature, a compiled expression can be stored and loaded without parsing it again.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(bound = ""))]
 pub struct Expression<T>
 where
     T: Library<T>,
@@ -24,6 +43,10 @@ where
     root: Option<ElementIndex>,
     /// Storage of the expression, containing [`Variables`] and the compiled [`Element`]s
     storage: ExpressionStorage<T>,
+    /// Conversions applied to function arguments
+    coercion: Coercion,
+    /// Handling of integer overflow
+    arithmetic: Arithmetic,
 }
 impl<T> Default for Expression<T>
 where
@@ -35,10 +58,53 @@ where
             string: Default::default(),
             root: Default::default(),
             storage: Default::default(),
+            coercion: Default::default(),
+            arithmetic: Default::default(),
         }
     }
 }
 
+/// Stored expressions are validated on load, so that evaluating them can't index out of bounds or loop.
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Expression", bound = "")]
+        struct Stored<T>
+        where
+            T: Library<T>,
+            [(); T::MAX_ARGS]:,
+        {
+            string: String,
+            root: Option<ElementIndex>,
+            storage: ExpressionStorage<T>,
+            coercion: Coercion,
+            arithmetic: Arithmetic,
+        }
+        let Stored {
+            string,
+            root,
+            mut storage,
+            coercion,
+            arithmetic,
+        } = Stored::deserialize(deserializer)?;
+        storage.validate(root).map_err(|error| {
+            serde::de::Error::custom(format_args!("invalid expression: {error:?}"))
+        })?;
+        Ok(Self {
+            string,
+            root,
+            storage,
+            coercion,
+            arithmetic,
+        })
+    }
+}
+
 impl<T> Expression<T>
 where
     T: Library<T>,
@@ -54,35 +120,549 @@ where
     pub fn set_expression(&mut self, expression: String) {
         self.string = expression;
     }
-    pub fn eval(&self) -> Result<Value, Error> {
-        /*
+    /// Sets the aliases and default namespaces used to resolve names, which applies to the next [`Expression::parse`].
+    pub fn set_scope(&mut self, scope: Scope) {
+        self.storage.scope = scope;
+    }
+    /// Sets whether literals with a fractional part, such as `0.1`, are parsed as exact [`Value::Decimal`]s
+    /// instead of floats, which applies to the next [`Expression::parse`].
+    #[cfg(feature = "decimal")]
+    pub fn set_decimal_literals(&mut self, decimal_literals: bool) {
+        self.storage.decimal_literals = decimal_literals;
+    }
+    /// Sets the conversions applied to function arguments, [`Coercion::Widening`] by default.
+    pub fn set_coercion(&mut self, coercion: Coercion) {
+        self.coercion = coercion;
+    }
+    /// Sets how integer overflow is handled by evaluation and constant folding, [`Arithmetic::Checked`] by default.
+    /// Integer division by zero always fails with [`Error::DivisionByZero`].
+    /// Library functions don't see this setting and always check, so `std::sum` and `std::abs`
+    /// fail with [`Error::Overflow`] in every mode.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+    /// Sets the value of a variable used by the expression.
+    /// Variables are only known after the expression has been parsed.
+    pub fn set_variable(&mut self, identifier: &str, value: impl Into<Value>) -> Result<(), Error> {
+        self.storage.variables.set(identifier, value.into())
+    }
+    /// Evaluates the expression, passing `context` to every function call.
+    pub fn eval_with_context(&self, context: &mut T::Context) -> Result<Value, Error> {
         match self.root {
-            Some(index) => self.eval_recursive(index),
+            Some(index) => self.eval_recursive(index, context, &mut HashMap::new()),
             None => Err(Error::NotCompiled),
         }
-        */
-        todo!()
-    }
-    /*
-    fn eval_recursive(&self, index: ElementIndex) -> Result<Value, Error> {
-        if let Element::Node(n) = &self.storage.get_element(index) {
-            Ok(match n {
-                Node::Instruction(Instruction { operator, lhs, rhs }) => {
-                    operator.eval(self.eval_recursive(*lhs)?, self.eval_recursive(*rhs)?)
-                }
-                Node::Literal(value) => *value,
-                Node::Variable(index) => self.storage.variables.get(*index),
-                Node::Function(Function { function, args }) => {
-                    let mut args_eval = SmallVec::<[Value; T::MAX_ARGS]>::new();
-                    for arg in args.iter() {
-                        args_eval.push(self.eval_recursive(*arg)?);
+    }
+    /// Evaluates the element at `index`, remembering the values of shared elements in `memo`.
+    fn eval_recursive(
+        &self,
+        index: ElementIndex,
+        context: &mut T::Context,
+        memo: &mut HashMap<ElementIndex, Value>,
+    ) -> Result<Value, Error> {
+        let shared = !self.storage.shared.is_empty() && self.storage.shared.contains(&index);
+        if let Some(value) = memo.get(&index).filter(|_| shared) {
+            return Ok(value.clone());
+        }
+        let value = match &self.storage.elements[index] {
+            Node::Instruction(Instruction { operator, lhs, rhs }) => operator.eval(
+                self.eval_recursive(*lhs, context, memo)?,
+                self.eval_recursive(*rhs, context, memo)?,
+                self.arithmetic,
+            )?,
+            Node::Literal(value) => value.clone(),
+            Node::Variable(index) => self.storage.variables[*index].clone(),
+            Node::Function(Function { function, args }) => {
+                let signature = function.signature();
+                let mut args_eval = SmallVec::<[Value; T::MAX_ARGS]>::new();
+                for (count, arg) in args.iter().enumerate() {
+                    let value = self.eval_recursive(*arg, context, memo)?;
+                    let expected = signature.param(count).ok_or(Error::InvalidArg)?;
+                    if !self.coercion.accepts(expected, value.value_type()) {
+                        return Err(Error::InvalidType);
                     }
-                    function.call(&args_eval)?
+                    args_eval.push(value);
                 }
-            })
-        } else {
-            Err(Error::InvalidIndex)
+                function.call(context, &args_eval)?
+            }
+            Node::List(List { items }) => Value::List(
+                items
+                    .iter()
+                    .map(|item| self.eval_recursive(*item, context, memo))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Node::Subscript(Subscript { list, index }) => {
+                let list: Vec<Value> = self.eval_recursive(*list, context, memo)?.try_into()?;
+                let index: i64 = self.eval_recursive(*index, context, memo)?.try_into()?;
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| list.into_iter().nth(index))
+                    .ok_or(Error::InvalidIndex)?
+            }
+        };
+        if shared {
+            memo.insert(index, value.clone());
         }
+        Ok(value)
     }
-    */
+}
+impl<T> Expression<T>
+where
+    T: Library<T, Context = dyn Any>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Evaluates the expression of a library which doesn't use a context.
+    pub fn eval(&self) -> Result<Value, Error> {
+        self.eval_with_context(&mut ())
+    }
+}
+#[test]
+fn eval_list() {
+    let mut expression = Expression::<Std>::new(String::from("[1, 2*3, 4][1] + xs[0]"));
+    expression.parse().unwrap();
+    expression
+        .set_variable("xs", vec![Value::Int(10), Value::Int(20)])
+        .unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Int(16));
+    expression.set_variable("xs", Vec::new()).unwrap();
+    assert!(matches!(expression.eval(), Err(Error::InvalidIndex)));
+
+    let mut expression = Expression::<Std>::new(String::from("[1]+1"));
+    expression.parse().unwrap();
+    assert!(matches!(expression.eval(), Err(Error::InvalidType)));
+}
+#[test]
+fn eval_arithmetic() {
+    for (arithmetic, product, quotient) in [
+        (Arithmetic::Checked, None, None),
+        (Arithmetic::Wrapping, Some(-2), Some(i64::MIN)),
+        (Arithmetic::Saturating, Some(i64::MAX), Some(i64::MAX)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from("x*2"));
+        expression.parse().unwrap();
+        expression.set_arithmetic(arithmetic);
+        expression.set_variable("x", i64::MAX).unwrap();
+        match product {
+            Some(product) => assert_eq!(expression.eval().unwrap(), Value::Int(product)),
+            None => assert!(matches!(expression.eval(), Err(Error::Overflow))),
+        }
+
+        let mut expression = Expression::<Std>::new(String::from("x/(0-1)"));
+        expression.parse().unwrap();
+        expression.set_arithmetic(arithmetic);
+        expression.fold_constants().unwrap();
+        expression.set_variable("x", i64::MIN).unwrap();
+        match quotient {
+            Some(quotient) => assert_eq!(expression.eval().unwrap(), Value::Int(quotient)),
+            None => assert!(matches!(expression.eval(), Err(Error::Overflow))),
+        }
+
+        let mut expression = Expression::<Std>::new(String::from("1%0+x"));
+        expression.parse().unwrap();
+        expression.set_arithmetic(arithmetic);
+        expression.fold_constants().unwrap();
+        assert_eq!(expression.to_string(), "1 % 0 + x");
+        assert!(matches!(expression.eval(), Err(Error::DivisionByZero)));
+
+        for (source, x) in [("sum(x, 1)", i64::MAX), ("abs(x)", i64::MIN)] {
+            let mut expression = Expression::<Std>::new(String::from(source));
+            expression.parse().unwrap();
+            expression.set_arithmetic(arithmetic);
+            expression.set_variable("x", x).unwrap();
+            assert!(
+                matches!(expression.eval(), Err(Error::Overflow)),
+                "{source}"
+            );
+        }
+    }
+    let mut expression = Expression::<Std>::new(String::from("x/0"));
+    expression.parse().unwrap();
+    assert!(matches!(expression.eval(), Err(Error::DivisionByZero)));
+    expression.set_variable("x", 1.5).unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Float(f64::INFINITY));
+}
+#[test]
+fn eval_list_functions() {
+    for (source, result) in [
+        ("len(xs)", Value::Int(3)),
+        ("sum(xs)", Value::Float(6.5)),
+        ("min(xs)", Value::Int(1)),
+        ("max(xs)", Value::Float(3.5)),
+        ("sum([])", Value::Int(0)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        expression
+            .set_variable("xs", vec![Value::Int(2), Value::Int(1), Value::Float(3.5)])
+            .ok();
+        assert_eq!(expression.eval().unwrap(), result, "{source}");
+    }
+}
+#[test]
+fn eval_variadic() {
+    for (source, result) in [
+        ("min(3, 1, 2)", Value::Int(1)),
+        ("max(1, [5, 2], 3)", Value::Int(5)),
+        ("sum(1, 2, 3, 4, 5, 6)", Value::Int(21)),
+        ("sum()", Value::Int(0)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert_eq!(expression.eval().unwrap(), result, "{source}");
+    }
+    assert!(matches!(
+        Std::len.call(&mut (), &[Value::List(vec![]), Value::List(vec![])]),
+        Err(Error::InvalidArgCount {
+            expected: Arity {
+                min: 1,
+                max: Some(1)
+            },
+            found: 2
+        })
+    ));
+}
+#[cfg(test)]
+mod test_library {
+    use crate::{
+        error::Error,
+        library::{Arity, FunctionInfo, Library, Signature},
+        value::{Type, Value},
+    };
+    use dyneval_derive::library;
+
+    library! {
+        Test; [];
+        [
+            fn half(x: f64) -> Result<Value, Error> {
+                Ok(Value::Float(x / 2.0))
+            },
+            fn even(x: i64) -> bool {
+                x % 2 == 0
+            },
+        ]
+    }
+
+    use crate::library::{dynamic::DynLibrary, std::Std};
+
+    #[cfg(feature = "decimal")]
+    pub use finance::Finance;
+    #[cfg(feature = "decimal")]
+    mod finance {
+        use crate::{
+            error::Error,
+            library::{Arity, FunctionInfo, Library, Signature},
+            value::{Decimal, Type, Value},
+        };
+        use dyneval_derive::library;
+
+        library! {
+            Finance; [];
+            [
+                /// `net` including 19% VAT.
+                const fn gross(net: Decimal) -> Decimal {
+                    net * Decimal::new(119, 2)
+                },
+            ]
+        }
+    }
+
+    library! {
+        Composed; [Std, DynLibrary];
+        [
+            fn twice(x: i64) -> Result<Value, Error> {
+                Ok(Value::Int(x * 2))
+            },
+        ]
+    }
+
+    library! {
+        #[namespace = "xl"]
+        Excel; [];
+        [
+            #[name = "SUM"]
+            #[alias = "sum"]
+            const fn sum_ints(#[rest] values: &[Value]) -> Result<Value, Error> {
+                values.iter().try_fold(Value::Int(0), |sum, value| {
+                    let sum: i64 = sum.try_into()?;
+                    let value: i64 = value.clone().try_into()?;
+                    Ok(Value::Int(sum + value))
+                })
+            },
+            #[name = "IF"]
+            const fn if_(condition: i64, then: Value, otherwise: Value) -> Result<Value, Error> {
+                Ok(if condition != 0 { then } else { otherwise })
+            },
+            #[name = "STDEV.S"]
+            const fn stdev_s(#[rest] values: &[Value]) -> Result<Value, Error> {
+                Ok(Value::Int(values.len() as i64))
+            },
+        ]
+    }
+
+    pub struct Counter {
+        pub calls: i64,
+    }
+    library! {
+        Counted; [];
+        [
+            fn next(#[context] counter: &mut Counter, step: i64) -> Result<Value, Error> {
+                counter.calls += step;
+                Ok(Value::Int(counter.calls))
+            },
+            /// Returns `x`, counting how often it is called.
+            const fn tally(#[context] counter: &mut Counter, x: i64) -> i64 {
+                counter.calls += 1;
+                x
+            },
+        ]
+    }
+    library! {
+        Extended; [Counted];
+        [
+            const fn double(x: i64) -> i64 {
+                x * 2
+            },
+        ]
+    }
+    // `Std` accepts any context, so the context of `Counted` is shared whatever the order
+    library! {
+        Reordered; [Std, Counted];
+        []
+    }
+}
+#[test]
+fn eval_coercion() {
+    use test_library::Test;
+
+    let mut expression = Expression::<Test>::new(String::from("half(3)"));
+    expression.parse().unwrap();
+    assert_eq!(expression.check().unwrap(), crate::value::Type::Any);
+    assert_eq!(expression.eval().unwrap(), Value::Float(1.5));
+
+    expression.set_coercion(Coercion::Strict);
+    assert!(matches!(
+        expression.check(),
+        Err(Error::TypeMismatch { position: 5, .. })
+    ));
+    assert!(matches!(expression.eval(), Err(Error::InvalidType)));
+
+    let mut expression = Expression::<Test>::new(String::from("even(4)+even(3)"));
+    expression.parse().unwrap();
+    assert_eq!(expression.check().unwrap(), crate::value::Type::Int);
+    assert_eq!(expression.eval().unwrap(), Value::Int(1));
+}
+#[test]
+fn eval_context() {
+    use test_library::{Counted, Counter, Extended, Reordered};
+
+    let mut expression = Expression::<Counted>::new(String::from("next(1)+next(2)*10"));
+    expression.parse().unwrap();
+    let mut counter = Counter { calls: 0 };
+    assert_eq!(
+        expression.eval_with_context(&mut counter).unwrap(),
+        Value::Int(31)
+    );
+    assert_eq!(counter.calls, 3);
+
+    let mut expression = Expression::<Extended>::new(String::from("double(counted::next(5))"));
+    expression.parse().unwrap();
+    assert_eq!(
+        expression.eval_with_context(&mut counter).unwrap(),
+        Value::Int(16)
+    );
+
+    let mut expression =
+        Expression::<Reordered>::new(String::from("std::abs(counted::next(0-20))"));
+    expression.parse().unwrap();
+    assert_eq!(
+        expression.eval_with_context(&mut counter).unwrap(),
+        Value::Int(12)
+    );
+}
+#[test]
+fn eval_composed() {
+    use crate::library::dynamic::DynLibrary;
+    use test_library::Composed;
+
+    // the registry is shared by all tests, and only this one registers `floor`,
+    // which clashes with `std::floor` on purpose
+    DynLibrary::register(
+        "floor",
+        Arity {
+            min: 1,
+            max: Some(1),
+        },
+        |args| Ok(args[0].clone()),
+    );
+    let mut expression = Expression::<Composed>::new(String::from(
+        "twice(std::max(1, 2, 3)) + composed::std::sqrt(16) + dyn::floor(1) + std::pi*0",
+    ));
+    expression.parse().unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Float(11.0));
+    assert!(Composed::functions().any(|info| info.qualified_name() == "composed::std::sqrt"));
+    assert!(Composed::functions().any(|info| info.qualified_name() == "composed::dyn::floor"));
+    const { assert!(Composed::MAX_ARGS >= Std::MAX_ARGS) };
+
+    let mut derivative = Expression::<Composed>::new(String::from("std::sin(x)*twice(1)"));
+    derivative.parse().unwrap();
+    assert_eq!(
+        derivative.derivative("x").unwrap().to_string(),
+        "composed::std::cos(x) * composed::twice(1)"
+    );
+
+    // every namespace is stripped once
+    let mut nested = Expression::<Composed>::new(String::from("composed::std::sqrt(4)"));
+    nested.parse().unwrap();
+    for source in [
+        "composed::composed::twice(1)",
+        "std::std::sqrt(4)",
+        "dyn::dyn::floor(1)",
+    ] {
+        nested.set_expression(String::from(source));
+        assert!(
+            matches!(nested.parse(), Err(Error::InvalidNamespace)),
+            "{source}"
+        );
+    }
+
+    let mut scope = Scope::default();
+    scope.use_namespace("std");
+    scope.use_namespace("dyn");
+    expression.set_scope(scope);
+    expression.set_expression(String::from("twice(sqrt(4))"));
+    expression.parse().unwrap();
+    assert!(matches!(expression.eval(), Err(Error::InvalidType)));
+    expression.set_expression(String::from("floor(1)"));
+    assert!(matches!(
+        expression.parse(),
+        Err(Error::AmbiguousIdentifier(identifier)) if identifier == "floor"
+    ));
+}
+#[test]
+fn eval_names() {
+    use test_library::Excel;
+
+    let mut expression = Expression::<Excel>::new(String::from(
+        "IF(0, 1, xl::SUM(1, 2)) + sum(3) + STDEV.S(1, 2)",
+    ));
+    expression.parse().unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Int(8));
+
+    for source in ["sum_ints(1)", "if_(1, 2, 3)", "excel::SUM(1)"] {
+        let mut expression = Expression::<Excel>::new(String::from(source));
+        assert!(expression.parse().is_err(), "{source}");
+    }
+    let info = Excel::sum_ints.info();
+    assert_eq!((info.name.as_ref(), info.aliases), ("SUM", &["sum"][..]));
+    assert_eq!(info.params, ["values"]);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use test_library::Composed;
+
+    let mut expression =
+        Expression::<Composed>::new(String::from("twice(x) + std::max([1, 2], 3)^2"));
+    expression.parse().unwrap();
+    expression.set_variable("x", 4).unwrap();
+    let json = serde_json::to_string(&expression).unwrap();
+    assert!(json.contains("\"composed::std::max\""));
+    let loaded: Expression<Composed> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.eval().unwrap(), Value::Int(17));
+
+    let renamed = json.replace("composed::twice", "composed::thrice");
+    assert!(serde_json::from_str::<Expression<Composed>>(&renamed).is_err());
+
+    let mut expression = Expression::<Composed>::new(String::from("x+1"));
+    expression.parse().unwrap();
+    let json = serde_json::to_value(&expression).unwrap();
+    for (pointer, index) in [
+        ("/root", 5),
+        ("/storage/elements/elements/1/Instruction/rhs", 7),
+        ("/storage/elements/elements/0/Variable", 1),
+        ("/storage/variables/identifiers/x", 1),
+        ("/storage/elements/elements/1/Instruction/lhs", 1),
+    ] {
+        let mut edited = json.clone();
+        *edited.pointer_mut(pointer).unwrap() = index.into();
+        assert!(
+            serde_json::from_value::<Expression<Composed>>(edited).is_err(),
+            "{pointer}"
+        );
+    }
+    assert!(serde_json::from_value::<Expression<Composed>>(json).is_ok());
+}
+#[cfg(feature = "decimal")]
+#[test]
+fn eval_decimal() {
+    use crate::value::{Decimal, Type};
+    use test_library::Finance;
+
+    let decimal = |string: &str| Value::Decimal(string.parse().unwrap());
+    for (source, result) in [
+        ("0.1+0.2", decimal("0.3")),
+        ("0.1*3+1", decimal("1.3")),
+        ("2.5^2", decimal("6.25")),
+        ("1.5e1+.5", decimal("15.5")),
+        ("1_000.25e-2+0x10", decimal("26.0025")),
+        ("1e-40+1", decimal("1")),
+        ("1234567e-30", decimal("0.0000000000000000000000012346")),
+        (
+            "0.000_000_000_000_000_000_000_000_000_005e2",
+            decimal("0.0000000000000000000000000005"),
+        ),
+        ("1.50+0.00001e5", decimal("2.50000")),
+        (
+            "79228162514264337593543950335e0",
+            Value::Decimal(Decimal::MAX),
+        ),
+        ("abs(0-1.5)", decimal("1.5")),
+        ("sum(1, 0.25, [0.5])", decimal("1.75")),
+        ("min(0.5, 1)", decimal("0.5")),
+        ("max(1.5, [0.25, 2])", Value::Int(2)),
+        ("min(0.5, 1.0)", decimal("0.5")),
+        ("max(0.1, 0.2, x)", Value::Float(0.25)),
+        ("clamp(0.35, 0.1, 0.3)", decimal("0.3")),
+        ("clamp(5, 0.5, 7)", decimal("5")),
+        ("clamp(0.5, x, 1)", Value::Float(0.5)),
+        ("0.5+x", Value::Float(0.75)),
+        ("sqrt(0.25)", Value::Float(0.5)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.set_decimal_literals(true);
+        expression.parse().unwrap();
+        expression.set_variable("x", 0.25).ok();
+        assert_eq!(expression.eval().unwrap(), result, "{source}");
+    }
+    let mut expression = Expression::<Std>::new(String::from("0.1+0.2"));
+    expression.parse().unwrap();
+    assert_ne!(expression.eval().unwrap(), Value::Float(0.3));
+    for source in [
+        "1e29",
+        "79228162514264337593543950336.0",
+        "1e9999999999999999999",
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.set_decimal_literals(true);
+        assert!(
+            matches!(expression.parse(), Err(Error::Overflow)),
+            "{source}"
+        );
+    }
+
+    let mut expression = Expression::<Std>::new(String::from("1.5/(x-1)+1"));
+    expression.set_decimal_literals(true);
+    expression.parse().unwrap();
+    assert_eq!(expression.check().unwrap(), Type::Number);
+    expression.set_variable("x", 1).unwrap();
+    assert!(matches!(expression.eval(), Err(Error::DivisionByZero)));
+    expression.set_variable("x", Decimal::new(25, 1)).unwrap();
+    assert_eq!(expression.eval().unwrap(), decimal("2"));
+    assert_eq!(expression.to_string(), "1.5 / (x - 1) + 1");
+
+    let mut expression = Expression::<Finance>::new(String::from("gross(10)+gross(0.5)"));
+    expression.set_decimal_literals(true);
+    expression.parse().unwrap();
+    assert_eq!(expression.check().unwrap(), Type::Decimal);
+    assert_eq!(expression.eval().unwrap(), decimal("12.495"));
 }
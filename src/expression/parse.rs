@@ -1,14 +1,21 @@
 extern crate test;
-use super::{expression_storage, Expression, ExpressionStorage};
+use super::{Expression, ExpressionStorage};
 use crate::{
     error::Error,
-    expression::element::{ElementIndex, Function, Instruction, Node, Operator::*},
-    library::{std::Std, Library},
+    expression::element::{
+        ElementIndex, Function, Instruction, List, Node, Operator::*, Subscript,
+    },
+    library::Library,
     value::Value,
 };
 use smallvec::SmallVec;
 use std::str;
 
+#[cfg(test)]
+use crate::library::{std::Std, Arity};
+#[cfg(feature = "decimal")]
+use crate::value::Decimal;
+
 trait Recoverable {
     fn if_recoverable<F>(self, f: F) -> Self
     where
@@ -29,43 +36,31 @@ impl<T, E> Recoverable for Result<T, Option<E>> {
 #[derive(Debug, Clone, Copy)]
 struct IndexWeight {
     weight: i16,
-    brackets: i16,
     index: ElementIndex,
 }
 impl Default for IndexWeight {
     fn default() -> Self {
         Self {
             weight: i16::MAX,
-            brackets: 0,
             index: ElementIndex(0),
         }
     }
 }
 impl IndexWeight {
-    fn assign_lower(&mut self, other: &Self) {
-        if other.weight < self.weight {
-            self.weight = other.weight;
-            self.index = other.index;
-        }
-    }
-    fn lower(self, other: Self) -> Self {
-        if other.weight < self.weight {
-            other
-        } else {
-            self
-        }
-    }
-    fn add(&mut self, weight: i16) {
-        self.weight += weight;
-    }
+    /// Weight of an expression which is not bound by any operator.
+    const ROOT: Self = Self {
+        weight: i16::MIN,
+        index: ElementIndex(0),
+    };
     fn new(index: ElementIndex) -> Self {
         IndexWeight {
             index,
             ..Default::default()
         }
     }
-    fn weight(&self) -> i16 {
-        self.weight + self.brackets * 30
+    /// Whether an operator of this weight binds to an operand before an operator of `other`'s weight.
+    fn binds(&self, other: &Self) -> bool {
+        self.weight > other.weight
     }
 }
 type ParseResult<'a> = std::result::Result<(&'a [u8], IndexWeight), Option<Error>>;
@@ -86,55 +81,62 @@ where
     [(); T::MAX_ARGS]:,
 {
     fn parse(&'a mut self, input: &'b [u8]) -> Result<ElementIndex, Error> {
-        let (_, result) = self.parse_expression_delimited(input, Default::default())?;
-        Ok(result.index)
+        self.clear();
+        self.length = input.len();
+        let (input, result) = self.parse_expression_partial(input, IndexWeight::ROOT)?;
+        match input.first() {
+            None => Ok(result.index),
+            Some(b')') => Err(Error::UnbalancedBracket),
+            Some(chr) => Err(Error::UnkownCharacter(*chr as char)),
+        }
     }
 
-    fn parse_expression_delimited(
-        &'a mut self,
-        input: &'b [u8],
-        mut weight: IndexWeight,
-    ) -> ParseResult<'b> {
+    fn parse_expression_delimited(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
         if let Some(b'(') = input.first() {
             let input = &input[1..];
-            weight.brackets += 1;
-            let (input, mut index) = self.parse_expression_delimited(input, weight)?;
+            let (input, index) = self.parse_expression_partial(input, IndexWeight::ROOT)?;
 
             if let Some(b')') = input.first() {
                 let input = &input[1..];
-                index.brackets -= 1;
-                Ok((input, index))
+                Ok((input, IndexWeight::new(index.index)))
             } else {
                 Err(Some(Error::UnbalancedBracket))
             }
         } else {
-            self.parse_expression_partial(input, weight)
+            Err(None)
         }
     }
+    /// Parses operands and every following operator which binds stronger than `weight`.
     fn parse_expression_partial(
         &'a mut self,
         input: &'b [u8],
         weight: IndexWeight,
     ) -> ParseResult<'b> {
-        let (input, operand) = self.parse_operand(input, weight)?;
-        let result = self.parse_operator(input);
-        result
-            .and_then(|(input, operator)| {
-                let (input, expression) = self.parse_expression_delimited(input, operator)?;
-
-                let index = weight.lower(expression);
-
-                let instruction = self.elements[operator.index].as_mut_instruction();
-                instruction.lhs = operand.index;
-                instruction.rhs = index.index;
+        let (mut input, mut operand) = self.parse_operand(input)?;
+        loop {
+            let result = self.parse_operator(input, weight);
+            match result {
+                Ok((input_temp, mut operator)) => {
+                    let instruction_weight = operator;
+                    if let Pow = self.elements[operator.index].as_instruction().operator {
+                        // right associative
+                        operator.weight -= 1;
+                    }
+                    let (input_temp, rhs) = self.parse_expression_partial(input_temp, operator)?;
+                    input = input_temp;
 
-                Ok((input, index))
-            })
-            .if_recoverable(|| self.parse_expression_delimited(input, weight))
-            .if_recoverable(|| Ok((input, operand)))
+                    let instruction = self.elements[operator.index].as_mut_instruction();
+                    instruction.lhs = operand.index;
+                    instruction.rhs = rhs.index;
+                    operand = instruction_weight;
+                }
+                Err(None) => break Ok((input, operand)),
+                Err(err) => break Err(err),
+            }
+        }
     }
-    fn parse_operator(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
-        if let Some(operator) = input.get(0) {
+    fn parse_operator(&'a mut self, input: &'b [u8], weight: IndexWeight) -> ParseResult<'b> {
+        if let Some(operator) = input.first() {
             let operator = match operator {
                 b'+' => Add,
                 b'-' => Sub,
@@ -144,114 +146,313 @@ where
                 b'^' => Pow,
                 _ => return Err(None),
             };
-            let index = self.elements.push_node(Node::Instruction(Instruction {
-                operator,
-                lhs: Default::default(),
-                rhs: Default::default(),
-            }));
+            let index_weight = IndexWeight {
+                weight: operator.weight(),
+                ..Default::default()
+            };
+            if !index_weight.binds(&weight) {
+                return Err(None);
+            }
+            let position = self.position(input);
+            let index = self.elements.push_node(
+                Node::Instruction(Instruction {
+                    operator,
+                    lhs: Default::default(),
+                    rhs: Default::default(),
+                }),
+                position,
+            );
             let input = &input[1..];
-            let mut index = IndexWeight::new(index);
-            index.weight = operator.weight();
-            Ok((input, index))
+            Ok((
+                input,
+                IndexWeight {
+                    index,
+                    ..index_weight
+                },
+            ))
         } else {
             Err(None)
         }
     }
-    fn parse_operand(&'a mut self, input: &'b [u8], weight: IndexWeight) -> ParseResult<'b> {
-        self.parse_literal(input)
-            .if_recoverable(|| self.parse_function(input, weight))
-            .if_recoverable(|| self.parse_identifier(input))
+    fn parse_operand(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
+        let (input, operand) = self
+            .parse_expression_delimited(input)
+            .if_recoverable(|| self.parse_list(input))
+            .if_recoverable(|| self.parse_literal(input))
+            .if_recoverable(|| self.parse_function(input))
+            .if_recoverable(|| self.parse_identifier(input))?;
+        self.parse_subscript(input, operand)
+    }
+    /// Parses any number of `[index]` following an operand.
+    fn parse_subscript(
+        &'a mut self,
+        mut input: &'b [u8],
+        mut list: IndexWeight,
+    ) -> ParseResult<'b> {
+        while let Some(b'[') = input.first() {
+            let position = self.position(input);
+            let (input_temp, index) =
+                self.parse_expression_partial(&input[1..], IndexWeight::ROOT)?;
+            match input_temp.first() {
+                Some(b']') => input = &input_temp[1..],
+                _ => return Err(Some(Error::UnbalancedBracket)),
+            }
+            let node = Node::Subscript(Subscript {
+                list: list.index,
+                index: index.index,
+            });
+            list = IndexWeight::new(self.elements.push_node(node, position));
+        }
+        Ok((input, list))
+    }
+    fn parse_list(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
+        let position = self.position(input);
+        let mut input = match input.first() {
+            Some(b'[') => &input[1..],
+            _ => return Err(None),
+        };
+        let mut items = Vec::new();
+        let input = if let Some(b']') = input.first() {
+            &input[1..]
+        } else {
+            loop {
+                let (input_temp, index) =
+                    self.parse_expression_partial(input, IndexWeight::ROOT)?;
+                input = input_temp;
+                items.push(index.index);
+                match input.first() {
+                    Some(b',') => input = &input[1..],
+                    Some(b']') => break &input[1..],
+                    _ => return Err(Some(Error::UnbalancedBracket)),
+                }
+            }
+        };
+        let index = self
+            .elements
+            .push_node(Node::List(List { items }), position);
+        let index = IndexWeight::new(index);
+        Ok((input, index))
     }
+    /// Parses a number literal: an integer such as `1_000`, `0x1F`, `0o17` or `0b1010`,
+    /// a number with a fraction or exponent such as `1.5`, `.5`, `1.` or `1e-3`, or `inf`.
     fn parse_literal(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
         if input.is_empty() {
             return Err(Some(Error::UnexpectedToken));
         }
-        let digits = input
-            .iter()
-            .position(|chr| !chr.is_ascii_digit())
-            .unwrap_or(input.len());
-
-        let (digits, input) = input.split_at(digits);
-
-        let (input, value) = if let Some(b'.') = input.first() {
-            let decimal_digits = input[1..]
-                .iter()
-                .position(|chr| !chr.is_ascii_digit())
-                .unwrap_or(input.len());
-
-            let (slice, input) = input.split_at(decimal_digits);
-
-            let float = str::from_utf8(slice)
-                .unwrap()
-                .parse()
-                .map_err(|_| Some(Error::InvalidType))?;
-
-            (input, Value::Float(float))
-        } else {
-            let int = str::from_utf8(digits).unwrap().parse().map_err(|_| None)?;
-
-            (input, Value::Int(int))
+        let position = self.position(input);
+        let (input, value) = match input {
+            [b'0', prefix @ (b'x' | b'X' | b'o' | b'O' | b'b' | b'B'), input @ ..] => {
+                let radix = match prefix.to_ascii_lowercase() {
+                    b'x' => 16,
+                    b'o' => 8,
+                    _ => 2,
+                };
+                let (input, digits) = digits(input, radix)?;
+                let int = i64::from_str_radix(&digits, radix).map_err(|_| Some(Error::Overflow))?;
+                (input, Value::Int(int))
+            }
+            [b'0'..=b'9', ..] | [b'.', b'0'..=b'9', ..] => self.parse_number(input)?,
+            _ => match identifier(input) {
+                // `inf(` and `inf::` are a function and a namespace
+                Ok((input, b"inf")) if !matches!(input.first(), Some(b'(' | b':')) => {
+                    (input, Value::Float(f64::INFINITY))
+                }
+                _ => return Err(None),
+            },
         };
 
-        let index = self.elements.push_node(Node::Literal(value));
+        let index = self.elements.push_node(Node::Literal(value), position);
         let index = IndexWeight::new(index);
         Ok((input, index))
     }
+    /// Parses a decimal integer, or a float if it has a fraction or an exponent.
+    fn parse_number(&self, input: &'b [u8]) -> Result<(&'b [u8], Value), Option<Error>> {
+        let (mut input, integer) = match input.first() {
+            Some(b'.') => (input, String::from("0")),
+            _ => digits(input, 10)?,
+        };
+        let mut fraction = None;
+        if let Some(b'.') = input.first() {
+            input = &input[1..];
+            fraction = Some(String::from("0"));
+            if input.first().is_some_and(u8::is_ascii_digit) {
+                let (input_temp, digits) = digits(input, 10)?;
+                input = input_temp;
+                fraction = Some(digits);
+            }
+        }
+        let mut exponent = None;
+        if let [b'e' | b'E', sign_digits @ ..] = input {
+            let (sign, exponent_digits) = match sign_digits {
+                [sign @ (b'+' | b'-'), exponent_digits @ ..] => {
+                    (Some(*sign as char), exponent_digits)
+                }
+                _ => (None, sign_digits),
+            };
+            // `2e` is followed by an identifier instead
+            if exponent_digits.first().is_some_and(u8::is_ascii_digit) {
+                let (input_temp, digits) = digits(exponent_digits, 10)?;
+                input = input_temp;
+                exponent = Some(format!("{}{digits}", sign.unwrap_or('+')));
+            }
+        }
+        if fraction.is_none() && exponent.is_none() {
+            let int = integer.parse().map_err(|_| Some(Error::Overflow))?;
+            return Ok((input, Value::Int(int)));
+        }
+        let fraction = fraction.unwrap_or_else(|| String::from("0"));
+        #[cfg(feature = "decimal")]
+        if self.decimal_literals {
+            let decimal = decimal(&integer, &fraction, exponent.as_deref())?;
+            return Ok((input, Value::Decimal(decimal)));
+        }
+        let exponent = exponent.unwrap_or_else(|| String::from("+0"));
+        let float = format!("{integer}.{fraction}e{exponent}")
+            .parse()
+            .map_err(|_| Some(Error::InvalidToken))?;
+        Ok((input, Value::Float(float)))
+    }
     fn parse_identifier(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
         if input.is_empty() {
             return Err(Some(Error::UnexpectedToken));
         }
-        match identifier(input) {
-            Ok((input, identifier)) => {
-                let identifier = str::from_utf8(identifier).unwrap();
-
-                let index = self.variables.find_or_set(identifier);
-                let index = self.elements.push_node(Node::Variable(index));
-                let index = IndexWeight::new(index);
+        let position = self.position(input);
+        let (input, namespaces) = namespaces(input);
+        let (input, identifier) = identifier(input)?;
+        let identifier = str::from_utf8(identifier).unwrap();
 
-                Ok((input, index))
+        let constant = self
+            .scope
+            .resolve(&namespaces, identifier, |namespaces, identifier| {
+                T::constant(namespaces, identifier).ok_or(Error::NoIdentifierMatch)
+            });
+        let node = match constant {
+            Ok(value) => Node::Literal(value),
+            Err(Error::NoIdentifierMatch) if namespaces.is_empty() => {
+                Node::Variable(self.variables.find_or_set(identifier))
             }
-            Err(err) => Err(err),
-        }
-    }
-    fn parse_function(&'a mut self, mut input: &'b [u8], weight: IndexWeight) -> ParseResult<'b> {
-        let mut namespaces = SmallVec::<[&str; 4]>::new();
+            Err(err) => return Err(Some(err)),
+        };
+        let index = self.elements.push_node(node, position);
+        let index = IndexWeight::new(index);
 
-        while let Ok((input_temp, namespace)) = namespace(input) {
-            input = input_temp;
-            namespaces.push(str::from_utf8(namespace).unwrap());
-        }
+        Ok((input, index))
+    }
+    fn parse_function(&'a mut self, input: &'b [u8]) -> ParseResult<'b> {
+        let position = self.position(input);
+        let (input, namespaces) = namespaces(input);
         let result =
-            identifier(input).map(|(input, identifier)| (input, identifier, input.first()));
+            function_name(input).map(|(input, identifier)| (input, identifier, input.first()));
         match result {
             Ok((input, identifier, Some(b'('))) => {
                 let mut input = &input[1..];
 
                 let identifier = str::from_utf8(identifier).unwrap();
-                let function = T::from_string(&namespaces, identifier)?;
+                let function = self
+                    .scope
+                    .resolve(&namespaces, identifier, T::from_string)?;
                 let mut args = SmallVec::new();
 
-                let input = loop {
-                    let (input_temp, index) = self.parse_expression_delimited(input, weight)?;
-                    input = input_temp;
-                    args.push(index.index);
-                    match input.first() {
-                        Some(b',') => input = &input[1..],
-                        Some(b')') => break &input[1..],
-                        _ => return Err(Some(Error::InvalidArg)),
+                let input = if let Some(b')') = input.first() {
+                    &input[1..]
+                } else {
+                    loop {
+                        let (input_temp, index) =
+                            self.parse_expression_partial(input, IndexWeight::ROOT)?;
+                        input = input_temp;
+                        args.push(index.index);
+                        match input.first() {
+                            Some(b',') => input = &input[1..],
+                            Some(b')') => break &input[1..],
+                            _ => return Err(Some(Error::InvalidArg)),
+                        }
                     }
                 };
+                let expected = function.arity();
+                if !expected.contains(args.len()) {
+                    return Err(Some(Error::InvalidArgCount {
+                        expected,
+                        found: args.len(),
+                    }));
+                }
                 let node = Node::Function(Function { function, args });
-                let index = self.elements.push_node(node);
+                let index = self.elements.push_node(node, position);
                 let index = IndexWeight::new(index);
                 Ok((input, index))
             }
             Err(Some(err)) => Err(Some(err)),
-            _ if namespaces.is_empty() => Err(None),
-            _ => Err(Some(Error::UnexpectedToken)),
+            _ => Err(None),
         }
     }
 }
+/// Parses digits of `radix` which may be separated by single underscores, and returns them without the underscores.
+fn digits(input: &[u8], radix: u32) -> Result<(&[u8], String), Option<Error>> {
+    let length = input
+        .iter()
+        .position(|chr| !((*chr as char).is_digit(radix) || *chr == b'_'))
+        .unwrap_or(input.len());
+    let (digits, input) = input.split_at(length);
+    let separated = digits.split(|chr| *chr == b'_');
+    if digits.is_empty() || separated.clone().any(<[u8]>::is_empty) {
+        return Err(Some(Error::InvalidToken));
+    }
+    let digits = separated
+        .map(|digits| str::from_utf8(digits).unwrap())
+        .collect();
+    Ok((input, digits))
+}
+/// Decimal of `integer.fraction` times ten to the power of `exponent`,
+/// whose digits beyond the 28 decimal places of [`Decimal`] are rounded, so that tiny numbers become zero.
+/// Only numbers too large for a decimal fail, with [`Error::Overflow`].
+#[cfg(feature = "decimal")]
+fn decimal(integer: &str, fraction: &str, exponent: Option<&str>) -> Result<Decimal, Error> {
+    let digits = format!("{integer}{fraction}");
+    let significant = digits.trim_start_matches('0');
+    if significant.is_empty() {
+        return Ok(Decimal::ZERO);
+    }
+    // exponents too large for an `i64` are out of range either way
+    let shift = match exponent {
+        Some(exponent) => exponent.parse().unwrap_or(if exponent.starts_with('-') {
+            i64::MIN
+        } else {
+            i64::MAX
+        }),
+        None => 0,
+    };
+    // position of the decimal point relative to the first significant digit
+    let point =
+        (integer.len() as i64 - (digits.len() - significant.len()) as i64).saturating_add(shift);
+    let plain = if point < -(Decimal::MAX_SCALE as i64) {
+        return Ok(Decimal::ZERO);
+    } else if point <= 0 {
+        format!(
+            "0.{}{significant}",
+            "0".repeat(point.unsigned_abs() as usize)
+        )
+    } else if (point as usize) < significant.len() {
+        let (whole, fraction) = significant.split_at(point as usize);
+        format!("{whole}.{fraction}")
+    } else if point <= 29 {
+        // `Decimal::MAX` has 29 digits
+        format!(
+            "{significant}{}",
+            "0".repeat(point as usize - significant.len())
+        )
+    } else {
+        return Err(Error::Overflow);
+    };
+    plain.parse().map_err(|_| Error::Overflow)
+}
+/// Parses any number of `namespace::` prefixes.
+fn namespaces(mut input: &[u8]) -> (&[u8], SmallVec<[&str; 4]>) {
+    let mut namespaces = SmallVec::new();
+    while let Ok((input_temp, namespace)) = namespace(input) {
+        input = input_temp;
+        namespaces.push(str::from_utf8(namespace).unwrap());
+    }
+    (input, namespaces)
+}
 fn namespace(input: &[u8]) -> Result<(&[u8], &[u8]), Option<Error>> {
     let (input, identifier) = identifier(input)?;
     if let Some(b"::") = input.get(0..2) {
@@ -262,14 +463,28 @@ fn namespace(input: &[u8]) -> Result<(&[u8], &[u8]), Option<Error>> {
     }
 }
 fn identifier(input: &[u8]) -> Result<(&[u8], &[u8]), Option<Error>> {
+    name(input, |chr| chr.is_ascii_alphanumeric() || chr == b'_')
+}
+/// Name of a called function, which may also contain dots such as `STDEV.S`.
+/// Names of variables and namespaces can't, so `x.5` or `a.b` aren't taken for a name.
+fn function_name(input: &[u8]) -> Result<(&[u8], &[u8]), Option<Error>> {
+    name(input, |chr| {
+        chr.is_ascii_alphanumeric() || matches!(chr, b'_' | b'.')
+    })
+}
+/// Splits off a name starting with a letter or `_`, followed by characters for which `is_name` holds.
+fn name(input: &[u8], is_name: impl Fn(u8) -> bool) -> Result<(&[u8], &[u8]), Option<Error>> {
     if input.is_empty() {
         return Err(Some(Error::UnexpectedToken));
     }
-    let is_first_alphabetic = input.get(0).copied().map(|chr| chr.is_ascii_alphabetic());
+    let is_first_alphabetic = input
+        .first()
+        .copied()
+        .map(|chr| chr.is_ascii_alphabetic() || chr == b'_');
     if let Some(true) = is_first_alphabetic {
         let characters = input
             .iter()
-            .position(|chr| !chr.is_ascii_alphanumeric())
+            .position(|chr| !is_name(*chr))
             .unwrap_or(input.len());
 
         let (slice, input) = input.split_at(characters);
@@ -282,18 +497,126 @@ fn identifier(input: &[u8]) -> Result<(&[u8], &[u8]), Option<Error>> {
 fn parse() {
     let a = [1, 2, 3, 4];
     let b = &a[0..=0];
-    dbg!(Std::from_string(&["std"], "print"));
+    dbg!(Std::from_string(&["std"], "print")).unwrap();
     dbg!(b);
     let mut expression = Expression::<Std>::new(String::from("1+2^4*6"));
-    dbg!(expression.parse());
+    dbg!(expression.parse()).unwrap();
     dbg!(&expression);
 }
+#[test]
+fn parse_literal() {
+    for (source, value) in [
+        ("1.5", Value::Float(1.5)),
+        (".5", Value::Float(0.5)),
+        ("1.", Value::Float(1.0)),
+        ("1.e2", Value::Float(100.0)),
+        ("1e-3", Value::Float(0.001)),
+        ("2.5E+2", Value::Float(250.0)),
+        ("1_000_000", Value::Int(1_000_000)),
+        ("0x1F", Value::Int(31)),
+        ("0Xff_ff", Value::Int(0xffff)),
+        ("0b1010", Value::Int(10)),
+        ("0o17", Value::Int(15)),
+        ("inf", Value::Float(f64::INFINITY)),
+        ("9223372036854775807", Value::Int(i64::MAX)),
+        ("0-9223372036854775807-1", Value::Int(i64::MIN)),
+        ("[1.5, 2][0]", Value::Float(1.5)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert_eq!(expression.eval().unwrap(), value, "{source}");
+
+        let mut reparsed = Expression::<Std>::new(expression.to_string());
+        reparsed.parse().unwrap();
+        assert_eq!(reparsed.eval().unwrap(), value, "{source}");
+    }
+    for (source, error) in [
+        ("9223372036854775808", Error::Overflow),
+        ("0x8000000000000000", Error::Overflow),
+        ("0x", Error::InvalidToken),
+        ("1__0", Error::InvalidToken),
+        ("1_", Error::InvalidToken),
+        ("1._5", Error::UnkownCharacter('_')),
+        ("2e", Error::UnkownCharacter('e')),
+        ("0b102", Error::UnkownCharacter('2')),
+        ("1.5.2", Error::UnkownCharacter('.')),
+        ("x.5", Error::UnkownCharacter('.')),
+        ("a.b+1", Error::UnkownCharacter('.')),
+        ("std.sqrt(4)", Error::UnknownFunction),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        let result = expression.parse();
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            format!("{error:?}"),
+            "{source}"
+        );
+    }
+    let mut expression = Expression::<Std>::new(String::from("info+inf"));
+    expression.parse().unwrap();
+    expression.set_variable("info", 1).unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Float(f64::INFINITY));
+
+    // `inf` is a literal in every library, not only one importing the std constant
+    let mut expression = Expression::<super::test_library::Test>::new(String::from("inf"));
+    expression.parse().unwrap();
+    assert!(expression.set_variable("inf", 1).is_err());
+    assert_eq!(expression.eval().unwrap(), Value::Float(f64::INFINITY));
+}
 #[bench]
 fn bench_parse(b: &mut test::Bencher) {
     let mut expression = Expression::<Std>::new(String::from("1+2^4*6"));
-    dbg!(expression.parse());
+    dbg!(expression.parse()).unwrap();
     dbg!(&expression);
     b.iter(|| {
-        expression.parse();
+        expression.parse().unwrap();
     })
 }
+#[test]
+fn parse_arity() {
+    for (source, found) in [("print(1,2)", 2), ("print()", 0), ("1+print(len([]),1)", 2)] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        assert!(matches!(
+            expression.parse(),
+            Err(Error::InvalidArgCount {
+                expected: Arity {
+                    min: 1,
+                    max: Some(1)
+                },
+                found: count
+            }) if count == found
+        ));
+    }
+    let mut expression = Expression::<Std>::new(String::from("max(1,2,3,4,5,6,7,8)"));
+    expression.parse().unwrap();
+}
+#[test]
+fn parse_constant() {
+    let mut expression = Expression::<Std>::new(String::from("pi*r^2"));
+    expression.parse().unwrap();
+    assert!(expression.set_variable("pi", 3).is_err());
+    assert!(expression.set_variable("r", 2).is_ok());
+
+    let mut expression = Expression::<Std>::new(String::from("std::x"));
+    assert!(matches!(expression.parse(), Err(Error::NoIdentifierMatch)));
+}
+#[test]
+fn parse_scope() {
+    use crate::expression::scope::Scope;
+
+    let mut scope = Scope::default();
+    scope.alias("s", "std");
+    let mut expression = Expression::<Std>::new(String::from("s::sqrt(s::pi^2)"));
+    expression.set_scope(scope);
+    expression.parse().unwrap();
+    assert_eq!(
+        expression.eval().unwrap(),
+        Value::Float(std::f64::consts::PI)
+    );
+
+    let mut expression = Expression::<Std>::new(String::from("s::sqrt(4)"));
+    assert!(matches!(expression.parse(), Err(Error::InvalidNamespace)));
+
+    let mut expression = Expression::<Std>::new(String::from("std::std::sqrt(4)"));
+    assert!(matches!(expression.parse(), Err(Error::InvalidNamespace)));
+}
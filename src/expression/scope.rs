@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+
+use crate::error::Error;
+
+/// Namespace aliases and default namespaces used to resolve functions and constants while parsing.
+///
+/// Names without a namespace are looked up in the library itself first,
+/// then in every default namespace, where they have to be unique.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scope {
+    aliases: HashMap<String, Vec<String>>,
+    defaults: Vec<Vec<String>>,
+}
+
+impl Scope {
+    /// Makes `alias::name` refer to `path::name`, for example `m` to `std::math`.
+    pub fn alias(&mut self, alias: &str, path: &str) {
+        self.aliases.insert(alias.to_owned(), split(path));
+    }
+    /// Searches `path` for names used without a namespace.
+    pub fn use_namespace(&mut self, path: &str) {
+        let path = split(path);
+        if !self.defaults.contains(&path) {
+            self.defaults.push(path);
+        }
+    }
+    /// Resolves `identifier` in `namespaces` with `resolve`, after expanding aliases.
+    /// Unqualified identifiers not found in the library itself are searched in the default namespaces.
+    pub(crate) fn resolve<R>(
+        &self,
+        namespaces: &[&str],
+        identifier: &str,
+        resolve: impl Fn(&[&str], &str) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        if let Some((first, rest)) = namespaces.split_first() {
+            return match self.aliases.get(*first) {
+                Some(path) => {
+                    let path = path
+                        .iter()
+                        .map(String::as_str)
+                        .chain(rest.iter().copied())
+                        .collect::<SmallVec<[&str; 4]>>();
+                    resolve(&path, identifier)
+                }
+                None => resolve(namespaces, identifier),
+            };
+        }
+        let error = match resolve(&[], identifier) {
+            Ok(resolved) => return Ok(resolved),
+            Err(error) => error,
+        };
+        let mut found = self.defaults.iter().filter_map(|path| {
+            let path = path
+                .iter()
+                .map(String::as_str)
+                .collect::<SmallVec<[&str; 4]>>();
+            resolve(&path, identifier).ok()
+        });
+        match (found.next(), found.next()) {
+            (Some(resolved), None) => Ok(resolved),
+            (Some(_), Some(_)) => Err(Error::AmbiguousIdentifier(identifier.to_owned())),
+            (None, _) => Err(error),
+        }
+    }
+}
+fn split(path: &str) -> Vec<String> {
+    path.split("::")
+        .filter(|namespace| !namespace.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
@@ -0,0 +1,466 @@
+use std::{any::Any, collections::HashMap};
+
+use super::{Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+    value::{Arithmetic, Coercion, Type, Value},
+};
+
+#[cfg(feature = "decimal")]
+use crate::value::Decimal;
+
+#[cfg(test)]
+use crate::library::std::Std;
+
+/// Identities applied by [`Expression::simplify`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Simplification {
+    /// Only rewrites which give the same value for every number, including NaN, infinities and signed zeros
+    #[default]
+    Strict,
+    /// Also rewrites which treat floats as real numbers, such as `x * 0 → 0`, `(a * b) / b → a`
+    /// and grouping like terms
+    FastMath,
+}
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Folds the constants of the expression and rewrites it with algebraic identities,
+    /// such as `x * 1 → x` or `x - x → 0` for integers, passing `context` to the functions called.
+    /// Operands of arithmetic are assumed to be numbers,
+    /// and subexpressions calling functions which aren't [`Library::is_const`] are never removed or merged.
+    pub fn simplify_with_context(
+        &mut self,
+        context: &mut T::Context,
+        simplification: Simplification,
+    ) -> Result<(), Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let (coercion, arithmetic) = (self.coercion, self.arithmetic);
+        self.storage.fold(root, coercion, arithmetic, Some(context));
+        let options = Options {
+            coercion,
+            arithmetic,
+            simplification,
+        };
+        let root = self.storage.simplify(root, options, &mut HashMap::new());
+        self.storage.fold(root, coercion, arithmetic, Some(context));
+        self.root = Some(root);
+        Ok(())
+    }
+}
+impl<T> Expression<T>
+where
+    T: Library<T, Context = dyn Any>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Simplifies an expression whose library doesn't use a context.
+    pub fn simplify(&mut self, simplification: Simplification) -> Result<(), Error> {
+        self.simplify_with_context(&mut (), simplification)
+    }
+}
+/// Settings of the expression which the identities depend on
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    coercion: Coercion,
+    arithmetic: Arithmetic,
+    simplification: Simplification,
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Simplifies the subtree at `index` from its leaves up and returns the index of its replacement.
+    /// `types` records the types of the simplified subtrees, which aren't changed afterwards.
+    fn simplify(
+        &mut self,
+        index: ElementIndex,
+        options: Options,
+        types: &mut HashMap<ElementIndex, Option<Type>>,
+    ) -> ElementIndex {
+        let operands = self.elements[index]
+            .operands_mut()
+            .into_iter()
+            .map(|operand| *operand)
+            .collect::<Vec<_>>();
+        let simplified = operands
+            .into_iter()
+            .map(|operand| self.simplify(operand, options, types))
+            .collect::<Vec<_>>();
+        for (operand, simplified) in self.elements[index]
+            .operands_mut()
+            .into_iter()
+            .zip(simplified)
+        {
+            *operand = simplified;
+        }
+        match &self.elements[index] {
+            Node::Instruction(_) => self.rewrite(index, options, types).unwrap_or(index),
+            _ => index,
+        }
+    }
+    /// Replacement of the instruction at `index` by an identity, if one applies.
+    fn rewrite(
+        &mut self,
+        index: ElementIndex,
+        options: Options,
+        types: &mut HashMap<ElementIndex, Option<Type>>,
+    ) -> Option<ElementIndex> {
+        let Node::Instruction(Instruction { operator, lhs, rhs }) = &self.elements[index] else {
+            return None;
+        };
+        let (operator, lhs, rhs) = (*operator, *lhs, *rhs);
+        let Options {
+            coercion,
+            arithmetic,
+            simplification,
+        } = options;
+        let fast_math = simplification == Simplification::FastMath;
+        let lhs_type = self.check_memoised(lhs, coercion, types).ok();
+        let rhs_type = self.check_memoised(rhs, coercion, types).ok();
+        let found = self.check_memoised(index, coercion, types).ok();
+        // identities which only hold for floats in fast math, as integers have no NaN or negative zero
+        let operand_type = |operand| if operand == lhs { lhs_type } else { rhs_type };
+        let exact = |operand| fast_math || operand_type(operand) == Some(Type::Int);
+        // an integer identity element keeps the type of the other operand, while a float one widens it
+        let keeps = |operand, literal| {
+            matches!(self.elements[literal], Node::Literal(Value::Int(_)))
+                || operand_type(operand) == Some(Type::Float)
+        };
+        let zero = |index| self.is_literal(index, 0, simplification);
+        let one = |index| self.is_literal(index, 1, simplification);
+        // strict mode also keeps subtrees whose errors would be hidden by removing them
+        let removable = |operand| {
+            exact(operand)
+                && self.is_pure(operand)
+                && (fast_math || self.is_infallible(operand, arithmetic))
+        };
+        match operator {
+            Operator::Add if zero(rhs) && exact(lhs) && keeps(lhs, rhs) => Some(lhs),
+            Operator::Add if zero(lhs) && exact(rhs) && keeps(rhs, lhs) => Some(rhs),
+            Operator::Sub if zero(rhs) && keeps(lhs, rhs) => Some(lhs),
+            Operator::Sub if self.same(lhs, rhs) && removable(lhs) => self.push_zero(index, found),
+            Operator::Mul if one(rhs) && keeps(lhs, rhs) => Some(lhs),
+            Operator::Mul if one(lhs) && keeps(rhs, lhs) => Some(rhs),
+            Operator::Mul if zero(rhs) && removable(lhs) => self.push_zero(index, found),
+            Operator::Mul if zero(lhs) && removable(rhs) => self.push_zero(index, found),
+            Operator::Div | Operator::Pow if one(rhs) && keeps(lhs, rhs) => Some(lhs),
+            // (a * b) / b = a
+            Operator::Div if fast_math && self.is_pure(rhs) => match &self.elements[lhs] {
+                Node::Instruction(Instruction {
+                    operator: Operator::Mul,
+                    lhs: a,
+                    rhs: b,
+                }) => {
+                    let (a, b) = (*a, *b);
+                    if self.same(b, rhs) {
+                        Some(a)
+                    } else if self.same(a, rhs) {
+                        Some(b)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            },
+            Operator::Add | Operator::Sub if fast_math => self.group(index, found),
+            _ => None,
+        }
+    }
+    /// Pushes a zero of type `found` in place of the element at `index`, unless it isn't a number.
+    /// A number of unknown type, which only fast math removes, becomes an integer zero,
+    /// as that is promoted to the type of any other operand.
+    fn push_zero(&mut self, index: ElementIndex, found: Option<Type>) -> Option<ElementIndex> {
+        let zero = match found? {
+            Type::Int | Type::Number | Type::Any => Value::Int(0),
+            Type::Float => Value::Float(0.0),
+            #[cfg(feature = "decimal")]
+            Type::Decimal => Value::Decimal(Decimal::ZERO),
+            Type::List => return None,
+        };
+        let position = self.elements.position(index);
+        Some(self.elements.push_node(zero, position))
+    }
+    /// Groups the like terms of the sum at `index` of type `found`, adding up their coefficients and constants,
+    /// if at least two of its terms are combined and no coefficient overflows.
+    /// Terms with a coefficient of zero are left out, unless they call functions which aren't [`Library::is_const`].
+    fn group(&mut self, index: ElementIndex, found: Option<Type>) -> Option<ElementIndex> {
+        let mut terms = Vec::new();
+        self.terms(index, true, &mut terms);
+        let mut constant: Option<Value> = None;
+        let mut groups: Vec<(Value, Option<ElementIndex>)> = Vec::new();
+        for (coefficient, term) in terms.iter().cloned() {
+            let Some(term) = term else {
+                constant = Some(match constant {
+                    Some(constant) => Operator::Add
+                        .eval(constant, coefficient, Arithmetic::Checked)
+                        .ok()?,
+                    None => coefficient,
+                });
+                continue;
+            };
+            let like = groups.iter_mut().find(|(_, other)| {
+                other.is_some_and(|other| self.same(other, term) && self.is_pure(term))
+            });
+            match like {
+                Some((sum, _)) => {
+                    *sum = Operator::Add
+                        .eval(sum.clone(), coefficient, Arithmetic::Checked)
+                        .ok()?
+                }
+                None => groups.push((coefficient, Some(term))),
+            }
+        }
+        let constants = terms.iter().filter(|(_, term)| term.is_none()).count();
+        if groups.len() + constants.min(1) == terms.len() {
+            return None;
+        }
+        groups.extend(constant.map(|constant| (constant, None)));
+
+        let position = self.elements.position(index);
+        let mut sum = None;
+        for (coefficient, term) in groups {
+            let negative = match coefficient {
+                Value::Int(int) => int < 0,
+                Value::Float(float) => float < 0.0,
+                _ => return None,
+            };
+            let magnitude = if negative {
+                Operator::Sub
+                    .eval(Value::Int(0), coefficient, Arithmetic::Checked)
+                    .ok()?
+            } else {
+                coefficient
+            };
+            let term = match term {
+                _ if (magnitude == Value::Int(0) || magnitude == Value::Float(0.0))
+                    && term.is_none_or(|term| self.is_pure(term)) =>
+                {
+                    continue
+                }
+                Some(term) if magnitude == Value::Int(1) => term,
+                Some(term) => {
+                    let magnitude = self.elements.push_node(magnitude, position);
+                    self.elements.push_node(
+                        Instruction {
+                            operator: Operator::Mul,
+                            lhs: magnitude,
+                            rhs: term,
+                        },
+                        position,
+                    )
+                }
+                None => self.elements.push_node(magnitude, position),
+            };
+            let (operator, lhs) = match sum {
+                Some(sum) => (
+                    if negative {
+                        Operator::Sub
+                    } else {
+                        Operator::Add
+                    },
+                    sum,
+                ),
+                None if negative => (
+                    Operator::Sub,
+                    self.elements.push_node(Value::Int(0), position),
+                ),
+                None => {
+                    sum = Some(term);
+                    continue;
+                }
+            };
+            sum = Some(self.elements.push_node(
+                Instruction {
+                    operator,
+                    lhs,
+                    rhs: term,
+                },
+                position,
+            ));
+        }
+        match sum {
+            Some(sum) => Some(sum),
+            None => self.push_zero(index, found),
+        }
+    }
+    /// Collects the terms of the sum at `index` as their coefficient and the element they multiply,
+    /// where constants have no element.
+    fn terms(
+        &self,
+        index: ElementIndex,
+        positive: bool,
+        terms: &mut Vec<(Value, Option<ElementIndex>)>,
+    ) {
+        let signed = |value: Value| {
+            if positive {
+                Some(value)
+            } else {
+                Operator::Sub
+                    .eval(Value::Int(0), value, Arithmetic::Checked)
+                    .ok()
+            }
+        };
+        let term = match &self.elements[index] {
+            Node::Instruction(Instruction {
+                operator: operator @ (Operator::Add | Operator::Sub),
+                lhs,
+                rhs,
+            }) => {
+                self.terms(*lhs, positive, terms);
+                self.terms(*rhs, positive == (*operator == Operator::Add), terms);
+                return;
+            }
+            Node::Literal(value @ (Value::Int(_) | Value::Float(_))) => {
+                signed(value.clone()).map(|value| (value, None))
+            }
+            Node::Instruction(Instruction {
+                operator: Operator::Mul,
+                lhs,
+                rhs,
+            }) => match (&self.elements[*lhs], &self.elements[*rhs]) {
+                (Node::Literal(value @ (Value::Int(_) | Value::Float(_))), _) => {
+                    signed(value.clone()).map(|value| (value, Some(*rhs)))
+                }
+                (_, Node::Literal(value @ (Value::Int(_) | Value::Float(_)))) => {
+                    signed(value.clone()).map(|value| (value, Some(*lhs)))
+                }
+                _ => signed(Value::Int(1)).map(|value| (value, Some(index))),
+            },
+            _ => signed(Value::Int(1)).map(|value| (value, Some(index))),
+        };
+        // keeps terms whose coefficient can't be negated as they are
+        terms.push(term.unwrap_or((Value::Int(1), Some(index))));
+    }
+    /// Whether the element at `index` is the literal `value`, which may also be a float in fast math.
+    fn is_literal(&self, index: ElementIndex, value: i64, simplification: Simplification) -> bool {
+        match &self.elements[index] {
+            Node::Literal(Value::Int(int)) => *int == value,
+            Node::Literal(Value::Float(float)) => {
+                simplification == Simplification::FastMath && *float == value as f64
+            }
+            _ => false,
+        }
+    }
+    /// Whether evaluating the subtree at `index`, which is of [`Type::Int`], can't fail,
+    /// because it only adds, subtracts or multiplies literals and variables without checking for overflow.
+    fn is_infallible(&self, index: ElementIndex, arithmetic: Arithmetic) -> bool {
+        match &self.elements[index] {
+            Node::Literal(_) | Node::Variable(_) => true,
+            Node::Instruction(Instruction {
+                operator: Operator::Add | Operator::Sub | Operator::Mul,
+                lhs,
+                rhs,
+            }) => {
+                arithmetic != Arithmetic::Checked
+                    && self.is_infallible(*lhs, arithmetic)
+                    && self.is_infallible(*rhs, arithmetic)
+            }
+            _ => false,
+        }
+    }
+    /// Whether the subtree at `index` doesn't call functions which aren't [`Library::is_const`].
+    pub(crate) fn is_pure(&self, index: ElementIndex) -> bool {
+        match &self.elements[index] {
+            Node::Literal(_) | Node::Variable(_) => true,
+            Node::Instruction(Instruction { lhs, rhs, .. }) => {
+                self.is_pure(*lhs) && self.is_pure(*rhs)
+            }
+            Node::Function(Function { function, args }) => {
+                function.is_const() && args.iter().all(|arg| self.is_pure(*arg))
+            }
+            Node::List(List { items }) => items.iter().all(|item| self.is_pure(*item)),
+            Node::Subscript(Subscript { list, index }) => {
+                self.is_pure(*list) && self.is_pure(*index)
+            }
+        }
+    }
+    /// Whether the subtrees at `lhs` and `rhs` are structurally identical.
+    pub(crate) fn same(&self, lhs: ElementIndex, rhs: ElementIndex) -> bool {
+        if lhs == rhs {
+            return true;
+        }
+        let all_same = |lhs: &[ElementIndex], rhs: &[ElementIndex]| {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| self.same(*lhs, *rhs))
+        };
+        match (&self.elements[lhs], &self.elements[rhs]) {
+            (Node::Literal(lhs), Node::Literal(rhs)) => lhs == rhs,
+            (Node::Variable(lhs), Node::Variable(rhs)) => lhs == rhs,
+            (Node::Instruction(lhs), Node::Instruction(rhs)) => {
+                lhs.operator == rhs.operator
+                    && self.same(lhs.lhs, rhs.lhs)
+                    && self.same(lhs.rhs, rhs.rhs)
+            }
+            (Node::Function(lhs), Node::Function(rhs)) => {
+                lhs.function.info().qualified_name() == rhs.function.info().qualified_name()
+                    && all_same(&lhs.args, &rhs.args)
+            }
+            (Node::List(lhs), Node::List(rhs)) => all_same(&lhs.items, &rhs.items),
+            (Node::Subscript(lhs), Node::Subscript(rhs)) => {
+                self.same(lhs.list, rhs.list) && self.same(lhs.index, rhs.index)
+            }
+            _ => false,
+        }
+    }
+}
+#[test]
+fn simplify() {
+    for (source, strict, fast_math) in [
+        ("x*1+0", "x + 0", "x"),
+        ("x^1/1-0", "x", "x"),
+        ("2*3+x*0", "6 + x * 0", "6"),
+        ("2*3+sqrt(x)*0", "6 + std::sqrt(x) * 0", "6.0"),
+        (
+            "len(y)*0+len(y)-len(y)",
+            "std::len(y) * 0 + std::len(y) - std::len(y)",
+            "0",
+        ),
+        ("(1/0)*0", "1 / 0 * 0", "0"),
+        ("print(1)*0", "std::print(1) * 0", "std::print(1) * 0"),
+        ("x-x", "x - x", "0"),
+        ("2*x-2*x", "2 * x - 2 * x", "0"),
+        ("sqrt(x)-sqrt(x)", "std::sqrt(x) - std::sqrt(x)", "0.0"),
+        ("(x*y)/y+(y*x)/y", "x * y / y + y * x / y", "2 * x"),
+        ("2*x+y+3*x-x", "2 * x + y + 3 * x - x", "4 * x + y"),
+        ("x+1+x+2", "x + 1 + x + 2", "2 * x + 3"),
+        ("x-y-x+y", "x - y - x + y", "0"),
+        (
+            "len(x)-len(y)-len(x)+len(y)",
+            "std::len(x) - std::len(y) - std::len(x) + std::len(y)",
+            "0",
+        ),
+        (
+            "0*print(x)+len(y)+len(y)",
+            "0 * std::print(x) + std::len(y) + std::len(y)",
+            "0 * std::print(x) + 2 * std::len(y)",
+        ),
+        ("y-2*x-x", "y - 2 * x - x", "y - 3 * x"),
+        (
+            "print(x)+print(x)",
+            "std::print(x) + std::print(x)",
+            "std::print(x) + std::print(x)",
+        ),
+        ("sqrt(x)*(4-3)", "std::sqrt(x)", "std::sqrt(x)"),
+    ] {
+        for (simplification, simplified) in [
+            (Simplification::Strict, strict),
+            (Simplification::FastMath, fast_math),
+        ] {
+            let mut expression = Expression::<Std>::new(String::from(source));
+            expression.parse().unwrap();
+            expression.simplify(simplification).unwrap();
+            assert_eq!(expression.to_string(), simplified, "{source}");
+        }
+    }
+
+    let mut expression = Expression::<Std>::new(String::from("x*y/y+0*x"));
+    expression.parse().unwrap();
+    expression.simplify(Simplification::FastMath).unwrap();
+    expression.set_variable("x", 3).unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Int(3));
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Expression, ExpressionStorage};
+use crate::{
+    error::Error,
+    expression::element::{ElementIndex, Function, Instruction, List, Node, Operator, Subscript},
+    library::Library,
+};
+
+#[cfg(test)]
+use crate::{library::std::Std, value::Value};
+
+/// Structure of a node whose operands have already been deduplicated
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    /// Debug representation, which tells integers from floats and keeps the sign of zero
+    Literal(String),
+    Variable(usize),
+    Instruction(Operator, ElementIndex, ElementIndex),
+    Function(String, Vec<ElementIndex>),
+    List(Vec<ElementIndex>),
+    Subscript(ElementIndex, ElementIndex),
+}
+
+impl<T> Expression<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Merges structurally identical subexpressions, so that e.g. `x^2+y^2` is only computed once
+    /// in `sqrt(x^2+y^2) / (x^2+y^2)`, and [`Expression::eval`] remembers the value of merged elements
+    /// for the rest of an evaluation.
+    /// Subexpressions calling functions which aren't [`Library::is_const`] are never merged.
+    pub fn eliminate_common_subexpressions(&mut self) -> Result<(), Error> {
+        let root = self.root.ok_or(Error::NotCompiled)?;
+        let storage = &mut self.storage;
+        let (root, _) = storage.deduplicate(root, &mut HashMap::new(), &mut HashMap::new());
+        let mut parents = HashMap::new();
+        storage.count_parents(root, &mut parents);
+        storage.shared = parents
+            .into_iter()
+            .filter(|(index, count)| {
+                *count > 1
+                    && !matches!(
+                        storage.elements[*index],
+                        Node::Literal(_) | Node::Variable(_)
+                    )
+            })
+            .map(|(index, _)| index)
+            .collect::<HashSet<_>>();
+        self.root = Some(root);
+        Ok(())
+    }
+}
+impl<T> ExpressionStorage<T>
+where
+    T: Library<T>,
+    [(); T::MAX_ARGS]:,
+{
+    /// Replaces the operands of the subtree at `index` by their first identical occurrence in `unique`,
+    /// and returns the index which replaces it and whether it is pure.
+    /// `visited` holds the result for elements which are already deduplicated.
+    fn deduplicate(
+        &mut self,
+        index: ElementIndex,
+        unique: &mut HashMap<Key, ElementIndex>,
+        visited: &mut HashMap<ElementIndex, (ElementIndex, bool)>,
+    ) -> (ElementIndex, bool) {
+        if let Some(result) = visited.get(&index) {
+            return *result;
+        }
+        let operands = self.elements[index]
+            .operands_mut()
+            .into_iter()
+            .map(|operand| *operand)
+            .collect::<Vec<_>>();
+        let mut pure = true;
+        let mut deduplicated = Vec::with_capacity(operands.len());
+        for operand in operands {
+            let (operand, operand_pure) = self.deduplicate(operand, unique, visited);
+            pure &= operand_pure;
+            deduplicated.push(operand);
+        }
+        for (operand, deduplicated) in self.elements[index]
+            .operands_mut()
+            .into_iter()
+            .zip(deduplicated)
+        {
+            *operand = deduplicated;
+        }
+        let key = match &self.elements[index] {
+            Node::Literal(value) => Key::Literal(format!("{value:?}")),
+            Node::Variable(variable) => Key::Variable(variable.0),
+            Node::Instruction(Instruction { operator, lhs, rhs }) => {
+                Key::Instruction(*operator, *lhs, *rhs)
+            }
+            Node::Function(Function { function, args }) => {
+                pure &= function.is_const();
+                Key::Function(function.info().qualified_name(), args.to_vec())
+            }
+            Node::List(List { items }) => Key::List(items.clone()),
+            Node::Subscript(Subscript { list, index }) => Key::Subscript(*list, *index),
+        };
+        let result = match pure {
+            true => (*unique.entry(key).or_insert(index), true),
+            false => (index, false),
+        };
+        visited.insert(index, result);
+        result
+    }
+    /// Counts how many elements reachable from `index` use each element as an operand.
+    fn count_parents(&mut self, index: ElementIndex, parents: &mut HashMap<ElementIndex, usize>) {
+        let count = parents.entry(index).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            return;
+        }
+        let operands = self.elements[index]
+            .operands_mut()
+            .into_iter()
+            .map(|operand| *operand)
+            .collect::<Vec<_>>();
+        for operand in operands {
+            self.count_parents(operand, parents);
+        }
+    }
+}
+#[test]
+fn eliminate_common_subexpressions() {
+    use super::test_library::{Counted, Counter};
+
+    let mut expression =
+        Expression::<Std>::new(String::from("sqrt(x^2+y^2)/(x^2+y^2)+print(x)-print(x)"));
+    expression.parse().unwrap();
+    let elements = expression.storage.elements.len();
+    expression.eliminate_common_subexpressions().unwrap();
+    let dot = expression.to_dot().unwrap();
+    let nodes = dot
+        .lines()
+        .filter(|line| line.contains("[label=\"") && !line.contains("->"))
+        .count();
+    assert_eq!(elements, 22);
+    assert_eq!(nodes, 12);
+    assert_eq!(
+        expression.to_string(),
+        "std::sqrt(x ^ 2 + y ^ 2) / (x ^ 2 + y ^ 2) + std::print(x) - std::print(x)"
+    );
+    expression.set_variable("x", 3).unwrap();
+    expression.set_variable("y", 4).unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Float(0.2 + 3.0 - 3.0));
+
+    let mut expression =
+        Expression::<Counted>::new(String::from("tally(2)*tally(2)+tally(x)+next(1)+next(1)"));
+    expression.parse().unwrap();
+    expression.eliminate_common_subexpressions().unwrap();
+    let mut counter = Counter { calls: 0 };
+    assert_eq!(
+        expression.eval_with_context(&mut counter).unwrap(),
+        Value::Int(11)
+    );
+    assert_eq!(counter.calls, 4);
+}
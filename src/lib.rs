@@ -0,0 +1,11 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+#![feature(string_remove_matches)]
+#![feature(test)]
+
+extern crate dyneval_derive;
+pub mod error;
+pub mod expression;
+pub mod library;
+mod small_string;
+pub mod value;
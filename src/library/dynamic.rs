@@ -0,0 +1,165 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use crate::{
+    error::Error,
+    library::{Arity, FunctionInfo, Library, Signature},
+    value::{Type, Value},
+};
+
+#[cfg(test)]
+use crate::expression::Expression;
+
+/// Function registered at runtime
+pub type DynFunction = Arc<dyn Fn(&[Value]) -> Result<Value, Error> + Send + Sync>;
+
+static FUNCTIONS: LazyLock<RwLock<HashMap<String, DynLibrary>>> = LazyLock::new(Default::default);
+
+/// A [`Library`] of closures registered at runtime.
+///
+/// The registry is a global shared by the whole process, including threads and tests running in parallel,
+/// so names should be unique to their user.
+/// It is only consulted when a name is resolved: while parsing, while deserializing and by [`Library::functions`].
+/// Expressions keep the functions they resolved, so replacing or unregistering a function
+/// doesn't affect expressions which were already parsed or deserialized.
+/// To use them together with other libraries, import `DynLibrary` into a `library!`.
+#[derive(Clone)]
+pub struct DynLibrary {
+    name: Arc<str>,
+    arity: Arity,
+    function: DynFunction,
+}
+impl DynLibrary {
+    /// Registers `function` under `name`, replacing any function previously registered as `name`
+    /// for the expressions parsed from now on.
+    pub fn register<F>(name: &str, arity: Arity, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, Error> + Send + Sync + 'static,
+    {
+        let library = DynLibrary {
+            name: name.into(),
+            arity,
+            function: Arc::new(function),
+        };
+        FUNCTIONS.write().unwrap().insert(name.to_owned(), library);
+    }
+    /// Removes the function registered as `name`, so that it can no longer be parsed or deserialized.
+    /// Already parsed or deserialized expressions can still call it.
+    pub fn unregister(name: &str) -> bool {
+        FUNCTIONS.write().unwrap().remove(name).is_some()
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+impl Debug for DynLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynLibrary")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+impl Library<DynLibrary> for DynLibrary {
+    const NAMESPACE: &'static str = "dyn";
+
+    const MAX_ARGS: usize = 4;
+
+    type Context = dyn Any;
+
+    fn from_string(namespaces: &[&str], identifier: &str) -> Result<DynLibrary, Error> {
+        let namespaces = match namespaces {
+            [namespace, rest @ ..] if *namespace == Self::NAMESPACE => rest,
+            _ => namespaces,
+        };
+        match namespaces {
+            [] => FUNCTIONS
+                .read()
+                .unwrap()
+                .get(identifier)
+                .cloned()
+                .ok_or(Error::UnknownFunction),
+            _ => Err(Error::InvalidNamespace),
+        }
+    }
+
+    fn call(&self, _context: &mut dyn Any, args: &[Value]) -> Result<Value, Error> {
+        if !self.arity.contains(args.len()) {
+            return Err(Error::InvalidArgCount {
+                expected: self.arity,
+                found: args.len(),
+            });
+        }
+        (self.function)(args)
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn signature(&self) -> Signature {
+        Signature {
+            params: &[],
+            rest: Some(Type::Any),
+            ret: Type::Any,
+        }
+    }
+
+    fn info(&self) -> FunctionInfo {
+        FunctionInfo {
+            namespaces: vec![Self::NAMESPACE],
+            name: Cow::Owned(self.name.to_string()),
+            aliases: &[],
+            params: &[],
+            arity: self.arity,
+            signature: self.signature(),
+            docs: "",
+        }
+    }
+
+    fn functions() -> impl Iterator<Item = FunctionInfo> {
+        let functions = FUNCTIONS.read().unwrap();
+        let functions: Vec<_> = functions.values().map(|function| function.info()).collect();
+        functions.into_iter()
+    }
+
+    /// Registered functions may have side effects.
+    fn is_const(&self) -> bool {
+        false
+    }
+}
+#[test]
+fn dyn_library() {
+    DynLibrary::register(
+        "dyn_library_twice",
+        Arity {
+            min: 1,
+            max: Some(1),
+        },
+        |args| match &args[0] {
+            Value::Int(int) => Ok(Value::Int(int * 2)),
+            _ => Err(Error::InvalidType),
+        },
+    );
+    let mut expression = Expression::<DynLibrary>::new(String::from(
+        "dyn_library_twice(3)+dyn::dyn_library_twice(1)",
+    ));
+    expression.parse().unwrap();
+    assert_eq!(expression.eval().unwrap(), Value::Int(8));
+
+    let mut other = Expression::<DynLibrary>::new(String::from("dyn_library_twice(1,2)"));
+    assert!(matches!(
+        other.parse(),
+        Err(Error::InvalidArgCount { found: 2, .. })
+    ));
+
+    assert!(DynLibrary::unregister("dyn_library_twice"));
+    assert_eq!(expression.eval().unwrap(), Value::Int(8));
+    let mut expression = Expression::<DynLibrary>::new(String::from("dyn_library_twice(3)"));
+    assert!(matches!(expression.parse(), Err(Error::UnknownFunction)));
+}
@@ -1,16 +1,116 @@
+pub mod dynamic;
 pub mod std;
 
-use crate::{error::Error, value::Value};
+use ::std::borrow::Cow;
+
+use crate::{
+    error::Error,
+    value::{Type, Value},
+};
+
+/// Number of arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    /// `None` if the function is variadic
+    pub max: Option<usize>,
+}
+impl Arity {
+    pub fn contains(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// Types of a function's parameters and its return value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub params: &'static [Type],
+    /// Type of each argument passed to a rest parameter
+    pub rest: Option<Type>,
+    pub ret: Type,
+}
+impl Signature {
+    /// Type expected for the argument at `index`, if the function accepts that many arguments.
+    pub fn param(&self, index: usize) -> Option<Type> {
+        self.params.get(index).copied().or(self.rest)
+    }
+}
+
+/// Description of a library function, for example to offer completions in an editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionInfo {
+    /// Namespaces qualifying the function, starting with the namespace of the library it was listed by
+    pub namespaces: Vec<&'static str>,
+    pub name: Cow<'static, str>,
+    /// Additional names the function can be called by
+    pub aliases: &'static [&'static str],
+    /// Names of the parameters, including a rest parameter
+    pub params: &'static [&'static str],
+    pub arity: Arity,
+    pub signature: Signature,
+    /// Documentation comment of the function
+    pub docs: &'static str,
+}
+impl FunctionInfo {
+    /// Qualifies the function with the namespace of a library importing it.
+    pub fn within(mut self, namespace: &'static str) -> Self {
+        self.namespaces.insert(0, namespace);
+        self
+    }
+    /// Name including all namespaces, for example `std::sqrt`.
+    pub fn qualified_name(&self) -> String {
+        let mut name = String::new();
+        for namespace in &self.namespaces {
+            name.push_str(namespace);
+            name.push_str("::");
+        }
+        name.push_str(&self.name);
+        name
+    }
+}
 
 pub trait Library<T>
 where
     T: Library<T>,
 {
     const NAMESPACE: &'static str;
+    /// Number of arguments stored inline by each function call.
+    /// Variadic functions may be called with more arguments than this.
     const MAX_ARGS: usize;
+    /// State of the host passed to every function call.
+    /// Libraries whose functions don't take a `#[context]` parameter share the context of their imports,
+    /// or accept any context as `dyn Any` if all of them do, regardless of the order of the imports.
+    /// All imported libraries which don't accept any context have to use the same one.
+    type Context: ?Sized;
     fn from_string(namespaces: &[&str], identifier: &str) -> Result<T, Error>;
-    fn call(&self, args: &[Value]) -> Result<Value, Error>;
+    /// Resolves a name as returned by [`FunctionInfo::qualified_name`], for example `std::sqrt`.
+    fn from_qualified_name(name: &str) -> Result<T, Error> {
+        let mut namespaces = name.split("::").collect::<Vec<_>>();
+        let identifier = namespaces.pop().ok_or(Error::UnknownFunction)?;
+        T::from_string(&namespaces, identifier)
+    }
+    /// Value of the named constant, which replaces the identifier while parsing.
+    fn constant(_namespaces: &[&str], _identifier: &str) -> Option<Value> {
+        None
+    }
+    fn call(&self, context: &mut Self::Context, args: &[Value]) -> Result<Value, Error>;
+    /// Number of arguments the function accepts, checked while parsing.
+    fn arity(&self) -> Arity;
+    /// Types of the function, used to check an expression before evaluating it.
+    fn signature(&self) -> Signature;
+    /// Description of the function.
+    fn info(&self) -> FunctionInfo;
+    /// Descriptions of all functions of the library, including imported ones.
+    fn functions() -> impl Iterator<Item = FunctionInfo>;
+    /// Source of the partial derivative with respect to the parameter at `param`,
+    /// in terms of the parameter names and resolved in the namespace of the function.
+    /// Declared with `#[derivative = "..."]` in `library!`.
+    fn derivative(&self, _param: usize) -> Option<&'static str> {
+        None
+    }
+    /// Whether the function always returns the same value for the same arguments,
+    /// without side effects. Functions are marked as such by declaring them `const fn` in `library!`.
     fn is_const(&self) -> bool {
         true
     }
-}
\ No newline at end of file
+}
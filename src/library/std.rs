@@ -1,13 +1,254 @@
-use crate::{error::Error, value::Value};
-use crate::library::Library;
+use std::{cmp::Ordering, num::TryFromIntError};
+
+use crate::library::{Arity, FunctionInfo, Library, Signature};
+use crate::{
+    error::Error,
+    expression::element::Operator,
+    value::{Arithmetic, Type, Value},
+};
+
+#[cfg(feature = "decimal")]
+use crate::value::Decimal;
 use dyneval_derive::*;
 
+#[cfg(test)]
+use crate::expression::Expression;
+
 library! {
     Std; [];
     [
+        /// Prints `val` and returns it.
         fn print(val: Value) -> Result<Value, Error> {
             println!("{val}");
             Ok(val)
         },
+        /// Number of elements of `list`.
+        const fn len(list: &[Value]) -> Result<i64, TryFromIntError> {
+            list.len().try_into()
+        },
+        /// Sum of all values, including the elements of lists.
+        /// Integers are always added with checked arithmetic.
+        const fn sum(#[rest] values: &[Value]) -> Result<Value, Error> {
+            flatten(values).try_fold(Value::Int(0), |sum, value| {
+                Operator::Add.eval(sum, value.clone(), Arithmetic::Checked)
+            })
+        },
+        /// Smallest of all values, including the elements of lists.
+        const fn min(#[rest] values: &[Value]) -> Result<Value, Error> {
+            extremum(values, Ordering::Less)
+        },
+        /// Largest of all values, including the elements of lists.
+        const fn max(#[rest] values: &[Value]) -> Result<Value, Error> {
+            extremum(values, Ordering::Greater)
+        },
+        /// Restricts `val` to the range from `min` to `max`.
+        const fn clamp(val: Value, min: Value, max: Value) -> Result<Value, Error> {
+            match (val, min, max) {
+                (Value::Int(val), Value::Int(min), Value::Int(max)) if min <= max => {
+                    Ok(Value::Int(val.clamp(min, max)))
+                }
+                #[cfg(feature = "decimal")]
+                (
+                    val @ (Value::Int(_) | Value::Decimal(_)),
+                    min @ (Value::Int(_) | Value::Decimal(_)),
+                    max @ (Value::Int(_) | Value::Decimal(_)),
+                ) => {
+                    let (val, min, max): (Decimal, Decimal, Decimal) =
+                        (val.try_into()?, min.try_into()?, max.try_into()?);
+                    if min <= max {
+                        Ok(Value::Decimal(val.clamp(min, max)))
+                    } else {
+                        Err(Error::InvalidArg)
+                    }
+                }
+                (val, min, max) => {
+                    let (val, min, max): (f64, f64, f64) =
+                        (val.try_into()?, min.try_into()?, max.try_into()?);
+                    if min <= max {
+                        Ok(Value::Float(val.clamp(min, max)))
+                    } else {
+                        Err(Error::InvalidArg)
+                    }
+                }
+            }
+        },
+        /// Absolute value of `val`, which overflows for `i64::MIN`.
+        #[derivative = "val / abs(val)"]
+        const fn abs(val: Value) -> Result<Value, Error> {
+            match val {
+                Value::Int(int) => int.checked_abs().map(Value::Int).ok_or(Error::Overflow),
+                Value::Float(float) => Ok(Value::Float(float.abs())),
+                #[cfg(feature = "decimal")]
+                Value::Decimal(decimal) => Ok(Value::Decimal(decimal.abs())),
+                _ => Err(Error::InvalidType),
+            }
+        },
+        /// Square root of `x`.
+        #[derivative = "1 / (2 * sqrt(x))"]
+        const fn sqrt(x: f64) -> f64 {
+            x.sqrt()
+        },
+        /// `e` raised to the power of `x`.
+        #[derivative = "exp(x)"]
+        const fn exp(x: f64) -> f64 {
+            x.exp()
+        },
+        /// Natural logarithm of `x`.
+        #[derivative = "1.0 / x"]
+        const fn ln(x: f64) -> f64 {
+            x.ln()
+        },
+        /// Base 10 logarithm of `x`.
+        #[derivative = "1 / (x * ln(10))"]
+        const fn log10(x: f64) -> f64 {
+            x.log10()
+        },
+        /// Logarithm of `x` to the given `base`.
+        #[derivative(base = "0 - ln(x) / (base * ln(base) ^ 2)", x = "1 / (x * ln(base))")]
+        const fn log(base: f64, x: f64) -> f64 {
+            x.log(base)
+        },
+        /// Sine of `x` in radians.
+        #[derivative = "cos(x)"]
+        const fn sin(x: f64) -> f64 {
+            x.sin()
+        },
+        /// Cosine of `x` in radians.
+        #[derivative = "0 - sin(x)"]
+        const fn cos(x: f64) -> f64 {
+            x.cos()
+        },
+        /// Tangent of `x` in radians.
+        #[derivative = "1 / cos(x) ^ 2"]
+        const fn tan(x: f64) -> f64 {
+            x.tan()
+        },
+        /// Arcsine of `x`, in radians.
+        #[derivative = "1 / sqrt(1 - x ^ 2)"]
+        const fn asin(x: f64) -> f64 {
+            x.asin()
+        },
+        /// Arccosine of `x`, in radians.
+        #[derivative = "0 - 1 / sqrt(1 - x ^ 2)"]
+        const fn acos(x: f64) -> f64 {
+            x.acos()
+        },
+        /// Arctangent of `x`, in radians.
+        #[derivative = "1.0 / (1 + x ^ 2)"]
+        const fn atan(x: f64) -> f64 {
+            x.atan()
+        },
+        /// Angle of the point (`x`, `y`), in radians.
+        #[derivative(y = "1.0 * x / (x ^ 2 + y ^ 2)", x = "0 - 1.0 * y / (x ^ 2 + y ^ 2)")]
+        const fn atan2(y: f64, x: f64) -> f64 {
+            y.atan2(x)
+        },
+        /// Length of the hypotenuse of a right triangle with sides `x` and `y`.
+        #[derivative(x = "x / hypot(x, y)", y = "y / hypot(x, y)")]
+        const fn hypot(x: f64, y: f64) -> f64 {
+            x.hypot(y)
+        },
+        /// Largest integer less than or equal to `x`.
+        #[derivative = "0"]
+        const fn floor(x: f64) -> f64 {
+            x.floor()
+        },
+        /// Smallest integer greater than or equal to `x`.
+        #[derivative = "0"]
+        const fn ceil(x: f64) -> f64 {
+            x.ceil()
+        },
+        /// Nearest integer to `x`, rounding half-way cases away from zero.
+        #[derivative = "0"]
+        const fn round(x: f64) -> f64 {
+            x.round()
+        },
+    ];
+    const [
+        pi: f64 = std::f64::consts::PI,
+        e: f64 = std::f64::consts::E,
+        tau: f64 = std::f64::consts::TAU,
+        inf: f64 = f64::INFINITY,
+        nan: f64 = f64::NAN,
     ]
-}
\ No newline at end of file
+}
+
+/// Iterates over `values`, replacing lists by their elements.
+fn flatten(values: &[Value]) -> impl Iterator<Item = &Value> {
+    values.iter().flat_map(|value| match value {
+        Value::List(list) => list.iter(),
+        value => std::slice::from_ref(value).iter(),
+    })
+}
+/// Returns the first value of the flattened `values` which compares as `ordering` to all others.
+/// Integers and decimals are compared exactly, and as floats if either is a float.
+fn extremum(values: &[Value], ordering: Ordering) -> Result<Value, Error> {
+    let mut values = flatten(values);
+    let first = values.next().ok_or(Error::InvalidArg)?;
+    values.try_fold(first.clone(), |extremum, value| {
+        let order = match (&extremum, value) {
+            (Value::Int(lhs), Value::Int(rhs)) => rhs.partial_cmp(lhs),
+            #[cfg(feature = "decimal")]
+            (
+                lhs @ (Value::Int(_) | Value::Decimal(_)),
+                rhs @ (Value::Int(_) | Value::Decimal(_)),
+            ) => {
+                let (lhs, rhs): (Decimal, Decimal) =
+                    (lhs.clone().try_into()?, rhs.clone().try_into()?);
+                rhs.partial_cmp(&lhs)
+            }
+            (lhs, rhs) => {
+                let (lhs, rhs): (f64, f64) = (lhs.clone().try_into()?, rhs.clone().try_into()?);
+                rhs.partial_cmp(&lhs)
+            }
+        };
+        Ok(if order == Some(ordering) {
+            value.clone()
+        } else {
+            extremum
+        })
+    })
+}
+#[test]
+fn math() {
+    for (source, result) in [
+        ("sqrt(16)", Value::Float(4.0)),
+        ("abs(0-3)", Value::Int(3)),
+        ("clamp(5, 1, 3)", Value::Int(3)),
+        ("clamp(sqrt(4), 3, 4)", Value::Float(3.0)),
+        ("log(2, 8)", Value::Float(3.0)),
+        ("floor(7/2*1)+ceil(sqrt(2))", Value::Float(5.0)),
+        ("hypot(3, 4)", Value::Float(5.0)),
+        ("round(tau/pi)", Value::Float(2.0)),
+        ("ln(std::e)", Value::Float(1.0)),
+        ("min(inf, 1)", Value::Int(1)),
+        ("atan2(0, 1)+sin(0)+asin(0)", Value::Float(0.0)),
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert_eq!(expression.eval().unwrap(), result, "{source}");
+    }
+    assert!(Std::sqrt.is_const());
+    assert!(!Std::print.is_const());
+
+    let info = Std::functions().find(|info| info.name == "log").unwrap();
+    assert_eq!(info.params, ["base", "x"]);
+    assert_eq!(info.docs, "Logarithm of `x` to the given `base`.");
+    assert_eq!(info.qualified_name(), "std::log");
+    assert_eq!(Std::functions().count(), 23);
+    assert!(matches!(
+        Std::clamp.call(&mut (), &[Value::Int(1), Value::Int(3), Value::Int(2)]),
+        Err(Error::InvalidArg)
+    ));
+    for source in [
+        "sum(9223372036854775807, 1)",
+        "abs(0-9223372036854775807-1)",
+    ] {
+        let mut expression = Expression::<Std>::new(String::from(source));
+        expression.parse().unwrap();
+        assert!(
+            matches!(expression.eval(), Err(Error::Overflow)),
+            "{source}"
+        );
+    }
+}
@@ -1,18 +1,15 @@
+#![allow(incomplete_features)]
 #![feature(generic_const_exprs)]
-#![feature(try_blocks)]
-#![feature(string_remove_matches)]
-#![feature(test)]
 
-use expression::Expression;
-use library::{std::Std, Library};
+use dyneval::{expression::Expression, library::std::Std};
+
+fn main() {
+    let mut expression = Expression::<Std>::new("1+print(1)".to_owned());
+    dbg!(expression.parse()).ok();
+    dbg!(&expression);
+    dbg!(expression.eval()).ok();
+}
 
-extern crate dyneval_derive;
-//use library::std::test_print;
-pub mod error;
-pub mod expression;
-pub mod library;
-mod small_string;
-pub mod value;
 #[cfg(test)]
 mod tests {
     #[test]
@@ -21,10 +18,3 @@ mod tests {
         assert_eq!(result, 4);
     }
 }
-
-fn main() {
-    let mut expression = Expression::<Std>::new("1+print(1)".to_owned());
-    dbg!(&expression);
-    dbg!(expression.eval());
-    return ();
-}
@@ -1,7 +1,11 @@
-use std::{borrow::Borrow, str::from_utf8_unchecked};
+use std::{
+    borrow::Borrow,
+    hash::{Hash, Hasher},
+    str::from_utf8_unchecked,
+};
 
 use smallvec::SmallVec;
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SmallString<const LENGTH: usize> {
     vec: SmallVec<[u8; LENGTH]>,
 }
@@ -18,3 +22,24 @@ impl<const SIZE: usize> Borrow<str> for SmallString<SIZE> {
         unsafe { from_utf8_unchecked(&self.vec) }
     }
 }
+
+#[cfg(feature = "serde")]
+impl<const SIZE: usize> serde::Serialize for SmallString<SIZE> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.borrow())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, const SIZE: usize> serde::Deserialize<'de> for SmallString<SIZE> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|string| string.as_str().into())
+    }
+}
+
+/// Hashes like `str` so lookups through [`Borrow<str>`] find the same entries.
+impl<const SIZE: usize> Hash for SmallString<SIZE> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let string: &str = self.borrow();
+        string.hash(state)
+    }
+}
@@ -2,17 +2,130 @@ use std::fmt::Display;
 
 use crate::error::Error;
 
+/// Exact decimal number with up to 28 significant digits
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
 /// Value type
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Int(i64),
     Float(f64),
+    /// Exact decimal, which integers are promoted to and which is converted to a float in operations with one
+    #[cfg(feature = "decimal")]
+    Decimal(Decimal),
+    List(Vec<Value>),
+}
+impl Value {
+    pub fn value_type(&self) -> Type {
+        match self {
+            Self::Int(_) => Type::Int,
+            Self::Float(_) => Type::Float,
+            #[cfg(feature = "decimal")]
+            Self::Decimal(_) => Type::Decimal,
+            Self::List(_) => Type::List,
+        }
+    }
 }
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Int(int) => int.fmt(f),
             Self::Float(float) => float.fmt(f),
+            #[cfg(feature = "decimal")]
+            Self::Decimal(decimal) => decimal.fmt(f),
+            Self::List(list) => {
+                write!(f, "[")?;
+                for (index, value) in list.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Type of a [`Value`], as far as it is known before evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Float,
+    #[cfg(feature = "decimal")]
+    Decimal,
+    List,
+    /// Either [`Type::Int`] or [`Type::Float`], or [`Type::Decimal`] with the `decimal` feature
+    Number,
+    /// Only known during evaluation
+    Any,
+}
+impl Type {
+    /// Whether a value of type `found` may be used where `self` is expected.
+    pub fn accepts(self, found: Type) -> bool {
+        match (self, found) {
+            (Self::Any, _) | (_, Self::Any) => true,
+            (Self::Number, Self::Int | Self::Float) | (Self::Int | Self::Float, Self::Number) => {
+                true
+            }
+            #[cfg(feature = "decimal")]
+            (Self::Number, Self::Decimal) | (Self::Decimal, Self::Number) => true,
+            (expected, found) => expected == found,
+        }
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            #[cfg(feature = "decimal")]
+            Self::Decimal => "decimal",
+            Self::List => "list",
+            Self::Number => "number",
+            Self::Any => "any",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Handling of integer arithmetic whose result doesn't fit in an `i64`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Arithmetic {
+    /// Fails with [`Error::Overflow`](crate::error::Error::Overflow)
+    #[default]
+    Checked,
+    /// Wraps around at the bounds of `i64`
+    Wrapping,
+    /// Clamps to `i64::MIN` or `i64::MAX`
+    Saturating,
+}
+
+/// Conversions applied to function arguments whose type differs from the declared parameter type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Coercion {
+    /// [`Type::Int`] arguments are converted to floats where [`Type::Float`] is expected,
+    /// and with the `decimal` feature to decimals where [`Type::Decimal`] is expected
+    /// while decimals are converted to floats
+    #[default]
+    Widening,
+    /// Arguments must match the declared type exactly
+    Strict,
+}
+impl Coercion {
+    /// Whether a value of type `found` may be passed where `expected` is declared.
+    pub fn accepts(&self, expected: Type, found: Type) -> bool {
+        match self {
+            Self::Widening if expected == Type::Float => Type::Number.accepts(found),
+            #[cfg(feature = "decimal")]
+            Self::Widening if expected == Type::Decimal => {
+                matches!(found, Type::Int | Type::Decimal | Type::Number | Type::Any)
+            }
+            _ => expected.accepts(found),
         }
     }
 }
@@ -27,6 +140,23 @@ impl From<f64> for Value {
         Self::Float(float)
     }
 }
+/// Booleans are represented as `1` and `0`.
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Self {
+        Self::Int(boolean.into())
+    }
+}
+#[cfg(feature = "decimal")]
+impl From<Decimal> for Value {
+    fn from(decimal: Decimal) -> Self {
+        Self::Decimal(decimal)
+    }
+}
+impl From<Vec<Value>> for Value {
+    fn from(list: Vec<Value>) -> Self {
+        Self::List(list)
+    }
+}
 
 impl TryInto<i64> for Value {
     type Error = Error;
@@ -34,17 +164,54 @@ impl TryInto<i64> for Value {
     fn try_into(self) -> Result<i64, Self::Error> {
         match self {
             Self::Int(int) => Ok(int),
-            Self::Float(_) => Err(Error::InvalidType),
+            _ => Err(Error::InvalidType),
         }
     }
 }
+/// Widens [`Value::Int`] and [`Value::Decimal`] to a float, which may lose precision.
 impl TryInto<f64> for Value {
     type Error = Error;
 
     fn try_into(self) -> Result<f64, Self::Error> {
         match self {
             Self::Float(float) => Ok(float),
-            Self::Int(_) => Err(Error::InvalidType),
+            Self::Int(int) => Ok(int as f64),
+            #[cfg(feature = "decimal")]
+            Self::Decimal(decimal) => decimal.try_into().map_err(|_| Error::InvalidType),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+/// Promotes [`Value::Int`] to an exact decimal.
+#[cfg(feature = "decimal")]
+impl TryInto<Decimal> for Value {
+    type Error = Error;
+
+    fn try_into(self) -> Result<Decimal, Self::Error> {
+        match self {
+            Self::Decimal(decimal) => Ok(decimal),
+            Self::Int(int) => Ok(int.into()),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+impl TryInto<Vec<Value>> for Value {
+    type Error = Error;
+
+    fn try_into(self) -> Result<Vec<Value>, Self::Error> {
+        match self {
+            Self::List(list) => Ok(list),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+impl<'a> TryInto<&'a [Value]> for &'a Value {
+    type Error = Error;
+
+    fn try_into(self) -> Result<&'a [Value], Self::Error> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(Error::InvalidType),
         }
     }
 }
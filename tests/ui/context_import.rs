@@ -0,0 +1,43 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use dyneval::{
+    error::Error,
+    library::{Arity, FunctionInfo, Library, Signature},
+    value::{Type, Value},
+};
+use dyneval_derive::library;
+
+pub struct Host;
+pub struct Other;
+
+library! {
+    Hosted; [];
+    [
+        fn get(#[context] _host: &mut Host) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+    ]
+}
+library! {
+    Broken; [Hosted];
+    [
+        fn other(#[context] _other: &mut Other) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+    ]
+}
+library! {
+    Elsewhere; [];
+    [
+        fn get(#[context] _other: &mut Other) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+    ]
+}
+library! {
+    Conflicting; [Hosted, Elsewhere];
+    []
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use dyneval_derive::library;
+
+struct Host;
+struct Other;
+
+library! {
+    Broken; [];
+    [
+        fn get(#[context] host: &Host) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+        fn set(#[context] host: &mut Host, x: i64) -> Result<Value, Error> {
+            Ok(Value::Int(x))
+        },
+        fn other(#[context] other: &mut Other) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+    ]
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use dyneval_derive::library;
+
+library! {
+    Broken; [];
+    [
+        #[derivative = "y"]
+        fn add(x: f64, y: f64) -> f64 {
+            x + y
+        },
+        #[derivative(z = "1")]
+        fn neg(x: f64) -> f64 {
+            0.0 - x
+        },
+    ]
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+use dyneval_derive::library;
+
+library! {
+    #[namespace(math)]
+    Broken; [];
+    [
+        #[name = 1]
+        fn one() -> Result<Value, Error> {
+            Ok(Value::Int(1))
+        },
+        #[alias = "one"]
+        fn uno() -> Result<Value, Error> {
+            Ok(Value::Int(1))
+        },
+    ]
+}
+library! {
+    #[namespace = "my::math"]
+    Unreachable; [];
+    [
+        #[name = "a b"]
+        #[alias = "9x"]
+        #[alias = "x-y"]
+        fn spaced() -> Result<Value, Error> {
+            Ok(Value::Int(1))
+        },
+        #[name = "STDEV.S"]
+        #[alias = "_stdev2"]
+        fn stdev() -> Result<Value, Error> {
+            Ok(Value::Int(1))
+        },
+    ]
+}
+library! {
+    #[namespace = "my.math"]
+    Dotted; [];
+    []
+}
+
+fn main() {}
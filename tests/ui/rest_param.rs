@@ -0,0 +1,15 @@
+use dyneval_derive::library;
+
+library! {
+    Broken; [];
+    [
+        fn first(#[rest] values: &[Value], x: i64) -> Result<Value, Error> {
+            Ok(values[0].clone())
+        },
+        fn count(#[rest] values: Vec<Value>) -> Result<Value, Error> {
+            Ok(Value::Int(values.len() as i64))
+        },
+    ]
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use dyneval_derive::library;
+
+library! {
+    Broken; [];
+    [
+        fn text(x: i64) -> Result<String, Error> {
+            Ok(x.to_string())
+        },
+        fn nested(x: i64) -> Result<Result<i64, Error>, Error> {
+            Ok(Ok(x))
+        },
+        fn checked(x: i64) -> Result<i64, Error> {
+            Ok(x)
+        },
+    ]
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use dyneval_derive::library;
+
+library! {
+    Broken; [];
+    [
+        fn double(self, x: i64) -> Result<Value, Error> {
+            Ok(Value::Int(x * 2))
+        },
+    ]
+}
+
+fn main() {}
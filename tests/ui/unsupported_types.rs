@@ -0,0 +1,16 @@
+use dyneval_derive::library;
+
+library! {
+    Broken; [];
+    [
+        fn repeat(text: String, count: usize) -> Result<Value, Error> {
+            Ok(Value::Int(0))
+        },
+        fn nothing(x: i64) {},
+        fn text(x: i64) -> String {
+            x.to_string()
+        },
+    ]
+}
+
+fn main() {}